@@ -0,0 +1,83 @@
+//! Zone transfer attempts against a target's authoritative nameservers.
+//!
+//! An open AXFR hands out every name in the zone at once, which is both a
+//! finding worth reporting and a complete replacement for the wordlist pass.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+
+use crate::dns::{self, DnsClient, RData, RecordType, ResourceRecord};
+use crate::engine::StopHandle;
+use crate::results::{HostRecord, Source};
+
+/// Result of asking one nameserver address for the zone
+#[derive(Debug)]
+pub struct Transfer {
+    pub nameserver: String,
+    pub addr: SocketAddr,
+    pub result: io::Result<Vec<ResourceRecord>>,
+}
+
+/// Resolve the NS records of `domain` to addresses, using `port` for every server
+pub fn nameservers(client: &DnsClient, domain: &str, port: u16) -> Vec<(String, SocketAddr)> {
+    let lookup = client.lookup(domain, RecordType::NS);
+    let mut names: Vec<String> = lookup
+        .response
+        .iter()
+        .flat_map(|response| &response.answers)
+        .filter_map(|record| match &record.data {
+            RData::NS(name) => Some(name.trim_end_matches('.').to_lowercase()),
+            _ => None,
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let mut servers = Vec::new();
+    for name in names {
+        for rtype in [RecordType::A, RecordType::AAAA] {
+            let lookup = client.lookup(&name, rtype);
+            if let Some(response) = &lookup.response {
+                for ip in response.addresses() {
+                    servers.push((name.clone(), SocketAddr::new(ip, port)));
+                }
+            }
+        }
+    }
+    servers
+}
+
+/// Try an AXFR of `domain` against each of its nameservers, one at a time until `stop` is set
+pub fn attempt(client: &DnsClient, domain: &str, port: u16, stop: &StopHandle) -> Vec<Transfer> {
+    nameservers(client, domain, port)
+        .into_iter()
+        .take_while(|_| !stop.is_stopped())
+        .map(|(nameserver, addr)| Transfer {
+            result: dns::zone_transfer(addr, domain, client.timeout()),
+            nameserver,
+            addr,
+        })
+        .collect()
+}
+
+/// Group transferred records by owner into host records, leaving out the apex itself
+pub fn hosts(domain: &str, records: &[ResourceRecord], server: SocketAddr) -> Vec<HostRecord> {
+    let suffix = format!(".{}", domain);
+    let mut owners: BTreeMap<String, Vec<ResourceRecord>> = BTreeMap::new();
+    for record in records {
+        let name = record.name.trim_end_matches('.').to_lowercase();
+        if name.ends_with(&suffix) {
+            owners.entry(name).or_default().push(record.clone());
+        }
+    }
+
+    owners
+        .into_iter()
+        .map(|(name, records)| {
+            let mut host = HostRecord::new(&name, Some(server), Source::Axfr);
+            host.absorb_records(&records);
+            host
+        })
+        .collect()
+}
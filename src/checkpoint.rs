@@ -0,0 +1,210 @@
+//! Checkpoints of the wordlist pass, so an interrupted scan can be resumed.
+//!
+//! Lookups finish out of order, so progress is kept as an offset below which
+//! every wordlist entry is done, plus the entries past it that finished early.
+//! Candidates whose lookups failed transiently are kept to be tried again.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dns;
+use crate::error::{Error, Result};
+use crate::outcome::Outcome;
+use crate::results::HostRecord;
+
+/// How often a running pass writes its checkpoint
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub domain: String,
+    /// SHA-1 of the wordlist, one entry per line
+    pub wordlist_hash: String,
+    pub wordlist_size: usize,
+    /// Every wordlist entry below this offset has been looked up
+    pub offset: usize,
+    /// Entries at or past `offset` that are already done
+    #[serde(default)]
+    pub done: BTreeSet<usize>,
+    /// Hostnames whose last lookup timed out or failed and must be tried again
+    #[serde(default)]
+    pub retry: BTreeSet<String>,
+    /// Hosts the pass found so far
+    #[serde(default)]
+    pub results: Vec<HostRecord>,
+}
+
+impl Checkpoint {
+    pub fn new(domain: &str, wordlist: &[String]) -> Self {
+        Checkpoint {
+            domain: domain.to_string(),
+            wordlist_hash: wordlist_hash(wordlist),
+            wordlist_size: wordlist.len(),
+            offset: 0,
+            done: BTreeSet::new(),
+            retry: BTreeSet::new(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| Error::Checkpoint(format!("Could not read checkpoint {}: {}", path.display(), err)))?;
+        serde_json::from_str(&contents)
+            .map_err(|err| Error::Checkpoint(format!("Invalid checkpoint {}: {}", path.display(), err)))
+    }
+
+    /// Write to a temporary file first so a crash mid-write keeps the previous checkpoint
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self).map_err(|err| Error::Checkpoint(err.to_string()))?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, json)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Refuse to resume a checkpoint taken for another target or wordlist
+    pub fn verify(&self, domain: &str, wordlist: &[String]) -> Result<()> {
+        if self.domain != domain {
+            return Err(Error::Checkpoint(format!("Checkpoint is for {}, not {}", self.domain, domain)));
+        }
+        if self.wordlist_hash != wordlist_hash(wordlist) {
+            return Err(Error::Checkpoint("Checkpoint was taken with a different wordlist".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn is_done(&self, index: usize) -> bool {
+        index < self.offset || self.done.contains(&index)
+    }
+
+    /// Number of wordlist entries already looked up
+    pub fn completed(&self) -> usize {
+        self.offset + self.done.len()
+    }
+
+    /// Mark the wordlist entry at `index` as looked up
+    pub fn complete(&mut self, index: usize) {
+        self.done.insert(index);
+        while self.done.remove(&self.offset) {
+            self.offset += 1;
+        }
+    }
+}
+
+pub fn wordlist_hash(wordlist: &[String]) -> String {
+    let mut hasher = sha1_smol::Sha1::new();
+    for word in wordlist {
+        hasher.update(word.as_bytes());
+        hasher.update(b"\n");
+    }
+    dns::hex(&hasher.digest().bytes())
+}
+
+/// Records the progress of a running pass and saves it every [`SAVE_INTERVAL`]
+pub struct Tracker {
+    path: PathBuf,
+    /// Candidate hostname -> wordlist offset, for the entries this pass looks up
+    offsets: HashMap<String, usize>,
+    state: Mutex<(Checkpoint, Instant)>,
+}
+
+impl Tracker {
+    pub fn new(path: &Path, checkpoint: Checkpoint, offsets: HashMap<String, usize>) -> Self {
+        Tracker {
+            path: path.to_path_buf(),
+            offsets,
+            state: Mutex::new((checkpoint, Instant::now())),
+        }
+    }
+
+    /// Note a finished lookup. Returns an error only when a periodic save failed.
+    pub fn finished(&self, hostname: &str, outcome: Outcome, found: Option<&HostRecord>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let (checkpoint, last_save) = &mut *state;
+        if let Some(&index) = self.offsets.get(hostname) {
+            checkpoint.complete(index);
+        }
+        if outcome.is_transient() {
+            checkpoint.retry.insert(hostname.to_string());
+        } else {
+            checkpoint.retry.remove(hostname);
+        }
+        if let Some(record) = found {
+            checkpoint.results.push(record.clone());
+        }
+
+        if last_save.elapsed() < SAVE_INTERVAL {
+            return Ok(());
+        }
+        *last_save = Instant::now();
+        checkpoint.save(&self.path)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.state.lock().unwrap().0.save(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::Source;
+
+    fn wordlist(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn offset_advances_over_entries_finished_out_of_order() {
+        let mut checkpoint = Checkpoint::new("example.com", &wordlist(&["a", "b", "c", "d"]));
+        checkpoint.complete(2);
+        checkpoint.complete(0);
+        assert_eq!((checkpoint.offset, checkpoint.completed()), (1, 2));
+        assert!(checkpoint.is_done(2) && !checkpoint.is_done(1));
+
+        checkpoint.complete(1);
+        assert_eq!(checkpoint.offset, 3);
+        assert!(checkpoint.done.is_empty());
+    }
+
+    #[test]
+    fn saved_progress_is_resumed() {
+        let words = wordlist(&["www", "mail", "vpn"]);
+        let path = std::env::temp_dir().join(format!("sub_crawler-checkpoint-{}.json", std::process::id()));
+        let offsets: HashMap<String, usize> =
+            words.iter().enumerate().map(|(index, word)| (format!("{}.example.com", word), index)).collect();
+        let tracker = Tracker::new(&path, Checkpoint::new("example.com", &words), offsets);
+
+        let found = HostRecord::new("www.example.com", None, Source::Bruteforce);
+        tracker.finished("www.example.com", Outcome::Resolved, Some(&found)).unwrap();
+        tracker.finished("vpn.example.com", Outcome::Timeout, None).unwrap();
+        tracker.save().unwrap();
+
+        let resumed = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        resumed.verify("example.com", &words).unwrap();
+        assert_eq!(resumed.offset, 1);
+        assert_eq!(resumed.done, BTreeSet::from([2]));
+        assert_eq!(resumed.retry, BTreeSet::from(["vpn.example.com".to_string()]));
+        assert_eq!(resumed.results.len(), 1);
+        assert_eq!(resumed.results[0].hostname, "www.example.com");
+    }
+
+    #[test]
+    fn other_targets_and_wordlists_are_rejected() {
+        let words = wordlist(&["www", "mail"]);
+        let checkpoint = Checkpoint::new("example.com", &words);
+        assert!(checkpoint.verify("example.com", &words).is_ok());
+        assert!(matches!(checkpoint.verify("example.org", &words), Err(Error::Checkpoint(_))));
+        assert!(matches!(checkpoint.verify("example.com", &wordlist(&["www", "vpn"])), Err(Error::Checkpoint(_))));
+        // Order matters too: offsets would point at other entries
+        assert!(matches!(checkpoint.verify("example.com", &wordlist(&["mail", "www"])), Err(Error::Checkpoint(_))));
+    }
+}
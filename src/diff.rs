@@ -0,0 +1,223 @@
+//! Differences between two stored scans of the same domain.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::history::ScanEntry;
+use crate::results::HostRecord;
+
+/// What changed for a host present in both scans
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HostChange {
+    pub hostname: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_addresses: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_addresses: Vec<String>,
+    /// CNAME chains before and after, when they differ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cnames: Option<CnameChange>,
+    /// Answers of other record types (MX, TXT, NS...) that differ, keyed by type.
+    /// A type only one of the scans queried shows up here too.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub records: BTreeMap<String, RecordsChange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CnameChange {
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RecordsChange {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanDiff {
+    pub domain: String,
+    /// Ids of the older and newer scan
+    pub from: u64,
+    pub to: u64,
+    /// One of the scans was interrupted, so a host may have disappeared only because it was not reached
+    pub partial: bool,
+    /// Hosts only in the newer scan, as it found them
+    pub appeared: Vec<HostRecord>,
+    /// Hosts only in the older scan, as it last saw them
+    pub disappeared: Vec<HostRecord>,
+    pub changed: Vec<HostChange>,
+}
+
+impl ScanDiff {
+    /// Compare `old` with `new`; every list is sorted by hostname
+    pub fn between(old: &ScanEntry, new: &ScanEntry) -> Self {
+        let before: BTreeMap<&str, &HostRecord> = old.results.iter().map(|record| (record.hostname.as_str(), record)).collect();
+        let after: BTreeMap<&str, &HostRecord> = new.results.iter().map(|record| (record.hostname.as_str(), record)).collect();
+
+        let appeared = after
+            .iter()
+            .filter(|(hostname, _)| !before.contains_key(*hostname))
+            .map(|(_, &record)| record.clone())
+            .collect();
+        let disappeared = before
+            .iter()
+            .filter(|(hostname, _)| !after.contains_key(*hostname))
+            .map(|(_, &record)| record.clone())
+            .collect();
+        let changed = before
+            .iter()
+            .filter_map(|(hostname, old)| after.get(hostname).and_then(|new| host_change(old, new)))
+            .collect();
+
+        ScanDiff {
+            domain: new.domain.clone(),
+            from: old.id,
+            to: new.id,
+            partial: old.interrupted || new.interrupted,
+            appeared,
+            disappeared,
+            changed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.appeared.is_empty() && self.disappeared.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn host_change(old: &HostRecord, new: &HostRecord) -> Option<HostChange> {
+    let before: BTreeSet<String> = old.addresses().into_iter().collect();
+    let after: BTreeSet<String> = new.addresses().into_iter().collect();
+    let change = HostChange {
+        hostname: new.hostname.clone(),
+        added_addresses: after.difference(&before).cloned().collect(),
+        removed_addresses: before.difference(&after).cloned().collect(),
+        cnames: (old.cnames != new.cnames).then(|| CnameChange { before: old.cnames.clone(), after: new.cnames.clone() }),
+        records: records_change(old, new),
+    };
+    let unchanged = change.added_addresses.is_empty()
+        && change.removed_addresses.is_empty()
+        && change.cnames.is_none()
+        && change.records.is_empty();
+    (!unchanged).then_some(change)
+}
+
+/// Values added and removed per record type; the order answers came in does not count
+fn records_change(old: &HostRecord, new: &HostRecord) -> BTreeMap<String, RecordsChange> {
+    let types: BTreeSet<&String> = old.records.keys().chain(new.records.keys()).collect();
+    types
+        .into_iter()
+        .filter_map(|rtype| {
+            let values = |record: &HostRecord| -> BTreeSet<String> { record.records.get(rtype).into_iter().flatten().cloned().collect() };
+            let (before, after) = (values(old), values(new));
+            let change = RecordsChange {
+                added: after.difference(&before).cloned().collect(),
+                removed: before.difference(&after).cloned().collect(),
+            };
+            (!change.added.is_empty() || !change.removed.is_empty()).then(|| (rtype.clone(), change))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::Source;
+
+    fn scan(id: u64, results: Vec<HostRecord>) -> ScanEntry {
+        ScanEntry {
+            id,
+            domain: "example.com".to_string(),
+            started_at: "2024-01-01T00:00:00Z".to_string(),
+            duration_secs: 1.0,
+            options: Vec::new(),
+            interrupted: false,
+            results,
+        }
+    }
+
+    fn host(hostname: &str, records: &[(&str, &[&str])]) -> HostRecord {
+        let mut record = HostRecord::new(hostname, None, Source::Bruteforce);
+        for (rtype, values) in records {
+            record.records.insert(rtype.to_string(), values.iter().map(|value| value.to_string()).collect());
+        }
+        record
+    }
+
+    fn addressed(hostname: &str, ips: &[[u8; 4]], cnames: &[&str]) -> HostRecord {
+        let mut record = host(hostname, &[]);
+        record.ipv4 = ips.iter().map(|&ip| ip.into()).collect();
+        record.cnames = cnames.iter().map(|cname| cname.to_string()).collect();
+        record
+    }
+
+    #[test]
+    fn hosts_are_sorted_into_appeared_disappeared_and_changed() {
+        let old = scan(1, vec![
+            addressed("www.example.com", &[[192, 0, 2, 1]], &[]),
+            addressed("old.example.com", &[[192, 0, 2, 2]], &[]),
+            addressed("cdn.example.com", &[[192, 0, 2, 3]], &["edge.cdn.net"]),
+            addressed("same.example.com", &[[192, 0, 2, 4]], &[]),
+        ]);
+        let new = scan(2, vec![
+            addressed("same.example.com", &[[192, 0, 2, 4]], &[]),
+            addressed("new.example.com", &[[192, 0, 2, 5]], &[]),
+            addressed("www.example.com", &[[192, 0, 2, 1], [192, 0, 2, 9]], &[]),
+            addressed("cdn.example.com", &[[192, 0, 2, 3]], &["edge2.cdn.net"]),
+        ]);
+
+        let diff = ScanDiff::between(&old, &new);
+        assert_eq!((diff.from, diff.to, diff.partial), (1, 2, false));
+        assert_eq!(diff.appeared.iter().map(|record| record.hostname.as_str()).collect::<Vec<_>>(), ["new.example.com"]);
+        assert_eq!(diff.disappeared.iter().map(|record| record.hostname.as_str()).collect::<Vec<_>>(), ["old.example.com"]);
+
+        let [cdn, www] = &diff.changed[..] else { panic!("{:?}", diff.changed) };
+        assert_eq!(cdn.hostname, "cdn.example.com");
+        assert!(cdn.added_addresses.is_empty() && cdn.removed_addresses.is_empty());
+        assert_eq!(cdn.cnames, Some(CnameChange { before: vec!["edge.cdn.net".to_string()], after: vec!["edge2.cdn.net".to_string()] }));
+        assert_eq!(www.hostname, "www.example.com");
+        assert_eq!(www.added_addresses, ["192.0.2.9"]);
+        assert!(www.removed_addresses.is_empty() && www.cnames.is_none());
+    }
+
+    #[test]
+    fn identical_scans_have_no_changes() {
+        let hosts = vec![addressed("www.example.com", &[[192, 0, 2, 1]], &["lb.example.net"])];
+        let diff = ScanDiff::between(&scan(1, hosts.clone()), &scan(2, hosts));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn an_interrupted_scan_makes_the_diff_partial() {
+        let mut old = scan(1, vec![addressed("www.example.com", &[[192, 0, 2, 1]], &[])]);
+        old.interrupted = true;
+        let diff = ScanDiff::between(&old, &scan(2, Vec::new()));
+        assert!(diff.partial);
+        assert_eq!(diff.disappeared.len(), 1);
+    }
+
+    #[test]
+    fn other_record_types_count_as_changes() {
+        let old = scan(1, vec![host("mail.example.com", &[("MX", &["10 mx1.example.com"]), ("TXT", &["v=spf1 -all"])])]);
+        let new = scan(2, vec![host("mail.example.com", &[("MX", &["10 mx2.example.com"]), ("TXT", &["v=spf1 -all"])])]);
+
+        let diff = ScanDiff::between(&old, &new);
+        assert_eq!(diff.changed.len(), 1);
+        let records = &diff.changed[0].records;
+        assert_eq!(records.keys().collect::<Vec<_>>(), ["MX"]);
+        assert_eq!(records["MX"].added, ["10 mx2.example.com"]);
+        assert_eq!(records["MX"].removed, ["10 mx1.example.com"]);
+    }
+
+    #[test]
+    fn reordered_answers_are_not_changes() {
+        let old = scan(1, vec![host("example.com", &[("NS", &["ns1.example.com", "ns2.example.com"])])]);
+        let new = scan(2, vec![host("example.com", &[("NS", &["ns2.example.com", "ns1.example.com"])])]);
+        assert!(ScanDiff::between(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,914 @@
+//! Minimal DNS wire-protocol client.
+//!
+//! Queries go out over UDP and are retried over TCP when the server sets the
+//! truncation bit. Only the record types the scanner cares about are decoded;
+//! everything else is kept as raw bytes.
+//!
+//! Each thread keeps one UDP socket per address family for all of its
+//! queries, so the number of open sockets follows the number of workers
+//! rather than the number of lookups in flight.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::outcome::{Lookup, Outcome};
+use crate::ratelimit::RateLimiter;
+use crate::resolvers::{Health, ResolverPool};
+
+// Resolver used when /etc/resolv.conf is missing or lists no nameservers
+const FALLBACK_NAMESERVER: &str = "1.1.1.1:53";
+
+// UDP payload size advertised through EDNS0
+const EDNS_UDP_SIZE: u16 = 1232;
+
+// "DNSSEC OK" flag in the OPT record's TTL field
+const EDNS_DO_BIT: u32 = 0x8000;
+
+const CLASS_IN: u16 = 1;
+
+// Delay before the first retry of a transient failure, and the cap on later ones
+const BACKOFF_BASE: Duration = Duration::from_millis(100);
+const BACKOFF_MAX: Duration = Duration::from_secs(2);
+
+// OS errors for running out of sockets on this machine: EMFILE and ENFILE, or WSAEMFILE and WSAENOBUFS
+#[cfg(unix)]
+const OUT_OF_SOCKETS: &[i32] = &[24, 23];
+#[cfg(windows)]
+const OUT_OF_SOCKETS: &[i32] = &[10024, 10055];
+#[cfg(not(any(unix, windows)))]
+const OUT_OF_SOCKETS: &[i32] = &[];
+
+thread_local! {
+    // This thread's UDP sockets for IPv4 and IPv6 servers, opened on first use
+    static UDP_SOCKETS: RefCell<[Option<UdpSocket>; 2]> = const { RefCell::new([None, None]) };
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    A,
+    NS,
+    CNAME,
+    SOA,
+    PTR,
+    MX,
+    TXT,
+    AAAA,
+    SRV,
+    OPT,
+    NSEC,
+    NSEC3,
+    CAA,
+    AXFR,
+    Other(u16),
+}
+
+impl RecordType {
+    pub fn code(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::NS => 2,
+            RecordType::CNAME => 5,
+            RecordType::SOA => 6,
+            RecordType::PTR => 12,
+            RecordType::MX => 15,
+            RecordType::TXT => 16,
+            RecordType::AAAA => 28,
+            RecordType::SRV => 33,
+            RecordType::OPT => 41,
+            RecordType::NSEC => 47,
+            RecordType::NSEC3 => 50,
+            RecordType::CAA => 257,
+            RecordType::AXFR => 252,
+            RecordType::Other(code) => code,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1 => RecordType::A,
+            2 => RecordType::NS,
+            5 => RecordType::CNAME,
+            6 => RecordType::SOA,
+            12 => RecordType::PTR,
+            15 => RecordType::MX,
+            16 => RecordType::TXT,
+            28 => RecordType::AAAA,
+            33 => RecordType::SRV,
+            41 => RecordType::OPT,
+            47 => RecordType::NSEC,
+            50 => RecordType::NSEC3,
+            252 => RecordType::AXFR,
+            257 => RecordType::CAA,
+            other => RecordType::Other(other),
+        }
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = String;
+
+    /// Accepts mnemonics in any case (`mx`, `AAAA`) or `TYPEnnn` codes
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let upper = value.trim().to_ascii_uppercase();
+        let rtype = match upper.as_str() {
+            "A" => RecordType::A,
+            "NS" => RecordType::NS,
+            "CNAME" => RecordType::CNAME,
+            "SOA" => RecordType::SOA,
+            "PTR" => RecordType::PTR,
+            "MX" => RecordType::MX,
+            "TXT" => RecordType::TXT,
+            "AAAA" => RecordType::AAAA,
+            "SRV" => RecordType::SRV,
+            "CAA" => RecordType::CAA,
+            other => match other.strip_prefix("TYPE").and_then(|code| code.parse::<u16>().ok()) {
+                Some(code) => RecordType::from_code(code),
+                None => return Err(format!("Unknown record type: {}", value)),
+            },
+        };
+        Ok(rtype)
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordType::Other(code) => write!(f, "TYPE{}", code),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RData {
+    A(Ipv4Addr),
+    AAAA(Ipv6Addr),
+    CNAME(String),
+    NS(String),
+    PTR(String),
+    MX { preference: u16, exchange: String },
+    TXT(Vec<String>),
+    SOA { mname: String, rname: String, serial: u32, refresh: u32, retry: u32, expire: u32, minimum: u32 },
+    SRV { priority: u16, weight: u16, port: u16, target: String },
+    CAA { flags: u8, tag: String, value: String },
+    NSEC { next: String, types: Vec<RecordType> },
+    NSEC3 { algorithm: u8, flags: u8, iterations: u16, salt: Vec<u8>, next_hash: Vec<u8>, types: Vec<RecordType> },
+    Unknown(Vec<u8>),
+}
+
+impl fmt::Display for RData {
+    /// Presentation format as it would appear in a zone file
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RData::A(ip) => write!(f, "{}", ip),
+            RData::AAAA(ip) => write!(f, "{}", ip),
+            RData::CNAME(name) | RData::NS(name) | RData::PTR(name) => write!(f, "{}", name),
+            RData::MX { preference, exchange } => write!(f, "{} {}", preference, exchange),
+            RData::TXT(strings) => {
+                let quoted: Vec<String> = strings
+                    .iter()
+                    .map(|s| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")))
+                    .collect();
+                write!(f, "{}", quoted.join(" "))
+            },
+            RData::SOA { mname, rname, serial, refresh, retry, expire, minimum } => write!(
+                f, "{} {} {} {} {} {} {}", mname, rname, serial, refresh, retry, expire, minimum
+            ),
+            RData::SRV { priority, weight, port, target } => {
+                write!(f, "{} {} {} {}", priority, weight, port, target)
+            },
+            RData::CAA { flags, tag, value } => write!(f, "{} {} \"{}\"", flags, tag, value),
+            RData::NSEC { next, types } => write!(f, "{} {}", next, type_list(types)),
+            RData::NSEC3 { algorithm, flags, iterations, salt, next_hash, types } => {
+                let salt = if salt.is_empty() { "-".to_string() } else { hex(salt) };
+                write!(f, "{} {} {} {} {} {}",
+                    algorithm, flags, iterations, salt, base32hex(next_hash), type_list(types)
+                )
+            },
+            RData::Unknown(raw) => write!(f, "\\# {} {}", raw.len(), hex(raw)),
+        }
+    }
+}
+
+fn type_list(types: &[RecordType]) -> String {
+    types.iter().map(|rtype| rtype.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lowercase base32 with the extended hex alphabet (RFC 4648), as used in NSEC3 owner names
+pub fn base32hex(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u64;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+    out
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceRecord {
+    pub name: String,
+    pub rtype: RecordType,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: RData,
+}
+
+/// Response codes defined in RFC 1035 and RFC 6895
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseCode {
+    NoError,
+    FormErr,
+    ServFail,
+    NxDomain,
+    NotImp,
+    Refused,
+    Other(u8),
+}
+
+impl ResponseCode {
+    fn from_code(code: u8) -> Self {
+        match code {
+            0 => ResponseCode::NoError,
+            1 => ResponseCode::FormErr,
+            2 => ResponseCode::ServFail,
+            3 => ResponseCode::NxDomain,
+            4 => ResponseCode::NotImp,
+            5 => ResponseCode::Refused,
+            other => ResponseCode::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub id: u16,
+    pub truncated: bool,
+    pub rcode: ResponseCode,
+    pub questions: Vec<(String, RecordType)>,
+    pub answers: Vec<ResourceRecord>,
+    pub authority: Vec<ResourceRecord>,
+}
+
+impl Message {
+    /// IPv4 and IPv6 addresses found in the answer section
+    pub fn addresses(&self) -> Vec<IpAddr> {
+        self.answers
+            .iter()
+            .filter_map(|record| match record.data {
+                RData::A(ip) => Some(IpAddr::V4(ip)),
+                RData::AAAA(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+static RNG_STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Cheap xorshift generator, good enough for query IDs and random labels
+pub fn random_u64() -> u64 {
+    let mut current = RNG_STATE.load(Ordering::Relaxed);
+    loop {
+        let mut next = if current == 0 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0x9E37_79B9_7F4A_7C15);
+            (nanos ^ ((std::process::id() as u64) << 32)) | 1
+        } else {
+            current
+        };
+        next ^= next << 13;
+        next ^= next >> 7;
+        next ^= next << 17;
+        match RNG_STATE.compare_exchange_weak(current, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return next,
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Encode a domain name as a sequence of length-prefixed labels
+pub fn encode_name(name: &str, out: &mut Vec<u8>) -> io::Result<()> {
+    for label in name.trim_end_matches('.').split('.').filter(|l| !l.is_empty()) {
+        if label.len() > 63 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Label too long in name: {}", name),
+            ));
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+    Ok(())
+}
+
+/// Build a recursive query for a single question, with an EDNS0 OPT record.
+/// `dnssec` sets the DO bit so servers include NSEC/NSEC3 proofs.
+pub fn build_query(id: u16, name: &str, rtype: RecordType, dnssec: bool) -> io::Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(64);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Standard query with recursion desired
+    packet.extend_from_slice(&0x0100u16.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    packet.extend_from_slice(&0u16.to_be_bytes()); // ANCOUNT
+    packet.extend_from_slice(&0u16.to_be_bytes()); // NSCOUNT
+    packet.extend_from_slice(&1u16.to_be_bytes()); // ARCOUNT
+    encode_name(name, &mut packet)?;
+    packet.extend_from_slice(&rtype.code().to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+
+    // OPT pseudo-record: root name, type, UDP size, extended rcode/flags, no options
+    packet.push(0);
+    packet.extend_from_slice(&RecordType::OPT.code().to_be_bytes());
+    packet.extend_from_slice(&EDNS_UDP_SIZE.to_be_bytes());
+    let flags: u32 = if dnssec { EDNS_DO_BIT } else { 0 };
+    packet.extend_from_slice(&flags.to_be_bytes());
+    packet.extend_from_slice(&0u16.to_be_bytes());
+    Ok(packet)
+}
+
+/// Bounds-checked cursor over a received packet
+struct Reader<'a> {
+    packet: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(packet: &'a [u8]) -> Self {
+        Reader { packet, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.packet.len())
+            .ok_or_else(|| invalid_data("Truncated DNS message"))?;
+        let slice = &self.packet[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Read a possibly compressed domain name starting at the cursor
+    fn name(&mut self) -> io::Result<String> {
+        let (name, next) = read_name_at(self.packet, self.pos)?;
+        self.pos = next;
+        Ok(name)
+    }
+}
+
+/// Decode a name at `start`, following compression pointers.
+/// Returns the name and the offset right after it in the original position.
+fn read_name_at(packet: &[u8], start: usize) -> io::Result<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut pos = start;
+    let mut resume_at = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *packet.get(pos).ok_or_else(|| invalid_data("Truncated domain name"))? as usize;
+        if len & 0xC0 == 0xC0 {
+            let low = *packet.get(pos + 1).ok_or_else(|| invalid_data("Truncated name pointer"))? as usize;
+            if resume_at.is_none() {
+                resume_at = Some(pos + 2);
+            }
+            jumps += 1;
+            if jumps > 64 {
+                return Err(invalid_data("Compression pointer loop"));
+            }
+            pos = ((len & 0x3F) << 8) | low;
+            continue;
+        }
+        if len == 0 {
+            pos += 1;
+            break;
+        }
+        let label = packet
+            .get(pos + 1..pos + 1 + len)
+            .ok_or_else(|| invalid_data("Truncated label"))?;
+        labels.push(String::from_utf8_lossy(label).to_lowercase());
+        pos += 1 + len;
+    }
+
+    Ok((labels.join("."), resume_at.unwrap_or(pos)))
+}
+
+fn parse_record(reader: &mut Reader) -> io::Result<ResourceRecord> {
+    let name = reader.name()?;
+    let rtype = RecordType::from_code(reader.u16()?);
+    let class = reader.u16()?;
+    let ttl = reader.u32()?;
+    let rdlength = reader.u16()? as usize;
+    let rdata_start = reader.pos;
+    let raw = reader.bytes(rdlength)?;
+
+    let data = match rtype {
+        RecordType::A if rdlength == 4 => RData::A(Ipv4Addr::new(raw[0], raw[1], raw[2], raw[3])),
+        RecordType::AAAA if rdlength == 16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(raw);
+            RData::AAAA(Ipv6Addr::from(octets))
+        },
+        RecordType::CNAME => RData::CNAME(read_name_at(reader.packet, rdata_start)?.0),
+        RecordType::NS => RData::NS(read_name_at(reader.packet, rdata_start)?.0),
+        RecordType::PTR => RData::PTR(read_name_at(reader.packet, rdata_start)?.0),
+        RecordType::MX if rdlength >= 3 => RData::MX {
+            preference: u16::from_be_bytes([raw[0], raw[1]]),
+            exchange: read_name_at(reader.packet, rdata_start + 2)?.0,
+        },
+        RecordType::TXT => {
+            let mut strings = Vec::new();
+            let mut rest = raw;
+            while let Some((&len, tail)) = rest.split_first() {
+                let len = (len as usize).min(tail.len());
+                strings.push(String::from_utf8_lossy(&tail[..len]).into_owned());
+                rest = &tail[len..];
+            }
+            RData::TXT(strings)
+        },
+        RecordType::SOA => {
+            let (mname, next) = read_name_at(reader.packet, rdata_start)?;
+            let (rname, next) = read_name_at(reader.packet, next)?;
+            let mut fields = Reader { packet: reader.packet, pos: next };
+            RData::SOA {
+                mname,
+                rname,
+                serial: fields.u32()?,
+                refresh: fields.u32()?,
+                retry: fields.u32()?,
+                expire: fields.u32()?,
+                minimum: fields.u32()?,
+            }
+        },
+        RecordType::SRV if rdlength >= 7 => RData::SRV {
+            priority: u16::from_be_bytes([raw[0], raw[1]]),
+            weight: u16::from_be_bytes([raw[2], raw[3]]),
+            port: u16::from_be_bytes([raw[4], raw[5]]),
+            target: read_name_at(reader.packet, rdata_start + 6)?.0,
+        },
+        RecordType::CAA if rdlength >= 2 && 2 + raw[1] as usize <= rdlength => {
+            let tag_end = 2 + raw[1] as usize;
+            RData::CAA {
+                flags: raw[0],
+                tag: String::from_utf8_lossy(&raw[2..tag_end]).into_owned(),
+                value: String::from_utf8_lossy(&raw[tag_end..]).into_owned(),
+            }
+        },
+        RecordType::NSEC => {
+            let (next, end) = read_name_at(reader.packet, rdata_start)?;
+            let bitmap = reader.packet.get(end..rdata_start + rdlength)
+                .ok_or_else(|| invalid_data("Truncated NSEC record"))?;
+            RData::NSEC { next, types: parse_type_bitmap(bitmap) }
+        },
+        RecordType::NSEC3 if rdlength >= 5 => {
+            let mut fields = Reader::new(raw);
+            let (algorithm, flags) = (fields.bytes(1)?[0], fields.bytes(1)?[0]);
+            let iterations = fields.u16()?;
+            let salt_len = fields.bytes(1)?[0] as usize;
+            let salt = fields.bytes(salt_len)?.to_vec();
+            let hash_len = fields.bytes(1)?[0] as usize;
+            let next_hash = fields.bytes(hash_len)?.to_vec();
+            let types = parse_type_bitmap(&raw[fields.pos..]);
+            RData::NSEC3 { algorithm, flags, iterations, salt, next_hash, types }
+        },
+        _ => RData::Unknown(raw.to_vec()),
+    };
+
+    Ok(ResourceRecord { name, rtype, class, ttl, data })
+}
+
+/// Decode the window/bitmap type list of NSEC and NSEC3 records (RFC 4034 4.1.2)
+fn parse_type_bitmap(mut bitmap: &[u8]) -> Vec<RecordType> {
+    let mut types = Vec::new();
+    while let [window, len, rest @ ..] = bitmap {
+        let len = (*len as usize).min(rest.len());
+        for (index, byte) in rest[..len].iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let code = (*window as u16) << 8 | (index * 8 + bit) as u16;
+                    types.push(RecordType::from_code(code));
+                }
+            }
+        }
+        bitmap = &rest[len..];
+    }
+    types
+}
+
+/// Parse a complete DNS message
+pub fn parse_message(packet: &[u8]) -> io::Result<Message> {
+    let mut reader = Reader::new(packet);
+    let id = reader.u16()?;
+    let flags = reader.u16()?;
+    let qdcount = reader.u16()?;
+    let ancount = reader.u16()?;
+    let nscount = reader.u16()?;
+    // The additional section is not needed
+    let _arcount = reader.u16()?;
+
+    let mut questions = Vec::with_capacity(qdcount as usize);
+    for _ in 0..qdcount {
+        let name = reader.name()?;
+        let rtype = RecordType::from_code(reader.u16()?);
+        let _class = reader.u16()?;
+        questions.push((name, rtype));
+    }
+
+    let mut answers = Vec::with_capacity(ancount as usize);
+    for _ in 0..ancount {
+        answers.push(parse_record(&mut reader)?);
+    }
+
+    let mut authority = Vec::with_capacity(nscount as usize);
+    for _ in 0..nscount {
+        authority.push(parse_record(&mut reader)?);
+    }
+
+    Ok(Message {
+        id,
+        truncated: flags & 0x0200 != 0,
+        rcode: ResponseCode::from_code((flags & 0x000F) as u8),
+        questions,
+        answers,
+        authority,
+    })
+}
+
+/// Read nameserver addresses from a resolv.conf style file
+pub fn system_nameservers() -> Vec<SocketAddr> {
+    let contents = fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+    let mut servers: Vec<SocketAddr> = contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("nameserver"), Some(addr)) => {
+                    // Strip IPv6 zone identifiers such as fe80::1%eth0
+                    let addr = addr.split('%').next().unwrap_or(addr);
+                    addr.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53))
+                },
+                _ => None,
+            }
+        })
+        .collect();
+
+    if servers.is_empty() {
+        servers.push(FALLBACK_NAMESERVER.parse().expect("Invalid fallback nameserver"));
+    }
+    servers
+}
+
+/// Blocking DNS client spreading queries over a resolver pool
+#[derive(Clone, Debug)]
+pub struct DnsClient {
+    pool: Arc<ResolverPool>,
+    limiter: Arc<RateLimiter>,
+    timeout: Duration,
+    retries: usize,
+}
+
+impl DnsClient {
+    pub fn new(pool: Arc<ResolverPool>, limiter: Arc<RateLimiter>, timeout: Duration, retries: usize) -> Self {
+        DnsClient { pool, limiter, timeout, retries }
+    }
+
+    pub fn pool(&self) -> &ResolverPool {
+        &self.pool
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Resolve `name`, retrying transient outcomes on the next resolver in the
+    /// pool with exponential backoff. The outcome of the final attempt is returned.
+    pub fn lookup(&self, name: &str, rtype: RecordType) -> Lookup {
+        self.lookup_with(name, rtype, false)
+    }
+
+    /// Like `lookup`, with the DO bit set so denial-of-existence records come back
+    pub fn lookup_dnssec(&self, name: &str, rtype: RecordType) -> Lookup {
+        self.lookup_with(name, rtype, true)
+    }
+
+    fn lookup_with(&self, name: &str, rtype: RecordType, dnssec: bool) -> Lookup {
+        let mut lookup = Lookup { outcome: Outcome::NetworkError, response: None, resolver: None };
+
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                thread::sleep(backoff(attempt));
+            }
+
+            let (index, server) = self.pool.next();
+            self.limiter.acquire(index);
+            let started = Instant::now();
+            let mut local_failure = false;
+            lookup = match query_server(server, name, rtype, dnssec, self.timeout) {
+                Ok(message) => Lookup {
+                    outcome: Outcome::from_response(&message),
+                    response: Some(message),
+                    resolver: Some(server),
+                },
+                Err(err) => {
+                    local_failure = is_local_failure(&err);
+                    Lookup { outcome: Outcome::from_error(&err), response: None, resolver: Some(server) }
+                },
+            };
+
+            let health = match lookup.outcome {
+                Outcome::Timeout => Health::Timeout,
+                outcome if outcome.is_transient() => Health::Error,
+                _ => Health::Success,
+            };
+            // Running out of sockets here says nothing about the resolver
+            if !local_failure {
+                self.pool.record(index, health, started.elapsed());
+            }
+            self.limiter.record(matches!(lookup.outcome, Outcome::ServFail | Outcome::Timeout));
+
+            if !lookup.outcome.is_transient() {
+                break;
+            }
+        }
+
+        lookup
+    }
+}
+
+/// Delay before retry `attempt`: doubles from BACKOFF_BASE up to BACKOFF_MAX, with jitter
+fn backoff(attempt: usize) -> Duration {
+    let exponential = BACKOFF_BASE
+        .checked_mul(1 << (attempt - 1).min(16))
+        .unwrap_or(BACKOFF_MAX)
+        .min(BACKOFF_MAX);
+    let jitter_range = exponential.as_millis() as u64 / 2 + 1;
+    exponential + Duration::from_millis(random_u64() % jitter_range)
+}
+
+/// Send one query to one server, falling back to TCP if the UDP answer is truncated
+pub fn query_server(
+    server: SocketAddr,
+    name: &str,
+    rtype: RecordType,
+    dnssec: bool,
+    timeout: Duration,
+) -> io::Result<Message> {
+    let id = random_u64() as u16;
+    let packet = build_query(id, name, rtype, dnssec)?;
+    let message = exchange_udp(server, &packet, timeout)?;
+    if message.truncated {
+        return exchange_tcp(server, &packet, timeout);
+    }
+    Ok(message)
+}
+
+/// Check that a response carries our query ID and echoes our question
+fn response_matches(query: &[u8], message: &Message) -> bool {
+    let id = u16::from_be_bytes([query[0], query[1]]);
+    if message.id != id {
+        return false;
+    }
+    match (parse_question(query), message.questions.first()) {
+        (Ok(asked), Some(echoed)) => asked.0.eq_ignore_ascii_case(&echoed.0) && asked.1 == echoed.1,
+        // Some servers omit the question section in error responses
+        (Ok(_), None) => true,
+        _ => false,
+    }
+}
+
+fn parse_question(query: &[u8]) -> io::Result<(String, RecordType)> {
+    let mut reader = Reader::new(query);
+    reader.bytes(12)?;
+    let name = reader.name()?;
+    Ok((name, RecordType::from_code(reader.u16()?)))
+}
+
+/// Whether `err` came from this machine running out of sockets rather than from the server
+fn is_local_failure(err: &io::Error) -> bool {
+    err.raw_os_error().is_some_and(|code| OUT_OF_SOCKETS.contains(&code))
+}
+
+fn exchange_udp(server: SocketAddr, packet: &[u8], timeout: Duration) -> io::Result<Message> {
+    UDP_SOCKETS.with(|sockets| {
+        let mut sockets = sockets.borrow_mut();
+        let slot = &mut sockets[usize::from(server.is_ipv6())];
+        let socket = match slot {
+            Some(socket) => socket,
+            None => {
+                let bind_addr: SocketAddr = if server.is_ipv4() {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+                slot.insert(UdpSocket::bind(bind_addr)?)
+            },
+        };
+        // Connecting again points the socket at this server, and unreachable ports still fail fast
+        socket.connect(server)?;
+        socket.send(packet)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = vec![0u8; 65535];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "DNS query timed out"));
+            }
+            socket.set_read_timeout(Some(remaining))?;
+            let len = socket.recv(&mut buf)?;
+            // Ignore late answers to earlier queries and stray or spoofed datagrams
+            match parse_message(&buf[..len]) {
+                Ok(message) if response_matches(packet, &message) => return Ok(message),
+                _ => continue,
+            }
+        }
+    })
+}
+
+/// Send a query over TCP with the two-byte length prefix from RFC 1035 4.2.2
+pub fn exchange_tcp(server: SocketAddr, packet: &[u8], timeout: Duration) -> io::Result<Message> {
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    write_framed(&mut stream, packet)?;
+    read_framed(&mut stream, packet)
+}
+
+fn write_framed(stream: &mut TcpStream, packet: &[u8]) -> io::Result<()> {
+    let mut framed = Vec::with_capacity(packet.len() + 2);
+    framed.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+    framed.extend_from_slice(packet);
+    stream.write_all(&framed)
+}
+
+/// Read one length-prefixed message answering `query`
+fn read_framed(stream: &mut TcpStream, query: &[u8]) -> io::Result<Message> {
+    let mut len_buf = [0u8; 2];
+    stream.read_exact(&mut len_buf)?;
+    let mut buf = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+    stream.read_exact(&mut buf)?;
+
+    let message = parse_message(&buf)?;
+    if !response_matches(query, &message) {
+        return Err(invalid_data("Mismatched DNS response ID"));
+    }
+    Ok(message)
+}
+
+/// Request a full zone transfer (RFC 5936) of `zone` from `server` over TCP.
+///
+/// The transfer may span several messages; it is complete once the SOA that
+/// opened it shows up a second time. Returns every record in between.
+pub fn zone_transfer(server: SocketAddr, zone: &str, timeout: Duration) -> io::Result<Vec<ResourceRecord>> {
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let packet = build_query(random_u64() as u16, zone, RecordType::AXFR, false)?;
+    write_framed(&mut stream, &packet)?;
+
+    let mut records = Vec::new();
+    let mut soa_seen = 0;
+    while soa_seen < 2 {
+        let message = read_framed(&mut stream, &packet)?;
+        if message.rcode != ResponseCode::NoError {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Zone transfer denied ({:?})", message.rcode),
+            ));
+        }
+        if message.answers.is_empty() {
+            return Err(invalid_data("Empty zone transfer message"));
+        }
+        for record in message.answers {
+            if record.rtype == RecordType::SOA {
+                soa_seen += 1;
+                // The closing SOA repeats the opening one
+                if soa_seen == 2 {
+                    break;
+                }
+            } else if soa_seen == 0 {
+                return Err(invalid_data("Zone transfer did not start with an SOA record"));
+            }
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+        let mut packet = Vec::new();
+        for field in [0x1234, flags, questions, answers, 0, 0] {
+            packet.extend(u16::to_be_bytes(field));
+        }
+        packet
+    }
+
+    fn record(packet: &mut Vec<u8>, owner: &[u8], rtype: RecordType, rdata: &[u8]) {
+        packet.extend(owner);
+        packet.extend(rtype.code().to_be_bytes());
+        packet.extend(CLASS_IN.to_be_bytes());
+        packet.extend(300u32.to_be_bytes());
+        packet.extend((rdata.len() as u16).to_be_bytes());
+        packet.extend(rdata);
+    }
+
+    /// www.example.com CNAME cdn.example.com, cdn.example.com A 192.0.2.1, with every repeated name compressed
+    fn compressed_response() -> Vec<u8> {
+        let mut packet = header(0x8180, 1, 2);
+        packet.extend(b"\x03www\x07example\x03com\x00");
+        packet.extend(RecordType::A.code().to_be_bytes());
+        packet.extend(CLASS_IN.to_be_bytes());
+        // The CNAME target follows the answer's 2-byte owner pointer and 10 bytes of fixed fields
+        let cname_at = packet.len() + 12;
+        // "cdn", then a pointer to "example.com" at offset 16
+        record(&mut packet, &[0xC0, 12], RecordType::CNAME, b"\x03cdn\xC0\x10");
+        record(&mut packet, &[0xC0, cname_at as u8], RecordType::A, &[192, 0, 2, 1]);
+        packet
+    }
+
+    #[test]
+    fn compression_pointers_are_followed() {
+        let message = parse_message(&compressed_response()).unwrap();
+        assert_eq!(message.id, 0x1234);
+        assert!(!message.truncated);
+        assert_eq!(message.rcode, ResponseCode::NoError);
+        assert_eq!(message.questions, [("www.example.com".to_string(), RecordType::A)]);
+
+        let [cname, a] = &message.answers[..] else { panic!("{:?}", message.answers) };
+        assert_eq!(cname.name, "www.example.com");
+        assert_eq!(cname.data, RData::CNAME("cdn.example.com".to_string()));
+        assert_eq!(a.name, "cdn.example.com");
+        assert_eq!(a.ttl, 300);
+        assert_eq!(a.data, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(message.addresses(), [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]);
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let packet = compressed_response();
+        for len in 0..packet.len() {
+            assert!(parse_message(&packet[..len]).is_err(), "parsed the first {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn pointer_loops_are_rejected() {
+        let mut packet = header(0x8180, 1, 0);
+        packet.extend([0xC0, 12, 0, 1, 0, 1]);
+        assert!(parse_message(&packet).is_err());
+    }
+
+    #[test]
+    fn truncation_bit_and_rcode_are_read() {
+        let message = parse_message(&header(0x8383, 0, 0)).unwrap();
+        assert!(message.truncated);
+        assert_eq!(message.rcode, ResponseCode::NxDomain);
+    }
+}
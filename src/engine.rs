@@ -0,0 +1,126 @@
+//! Work-queue scanning engine.
+//!
+//! Candidates sit in one shared queue and a fixed number of workers pull the
+//! next one as soon as they are free, so a slow lookup only ever holds up the
+//! worker running it. Each worker keeps exactly one query in flight, which
+//! makes the worker count the concurrency limit.
+//!
+//! A [`StopHandle`] ends every run early: workers finish the lookup in hand
+//! and take nothing new from the queue.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// Workers block on sockets and need very little stack
+const WORKER_STACK_SIZE: usize = 256 * 1024;
+
+/// Upper bound on concurrent lookups
+pub const MAX_CONCURRENCY: usize = 10_000;
+
+/// Stops an [`Engine`] from another thread, such as a signal handler
+#[derive(Clone, Debug, Default)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+type Warning = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Engine {
+    concurrency: usize,
+    stop: StopHandle,
+    on_warning: Option<Warning>,
+}
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Engine")
+            .field("concurrency", &self.concurrency)
+            .field("stop", &self.stop)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Engine {
+    pub fn new(concurrency: usize) -> Self {
+        Engine { concurrency: concurrency.clamp(1, MAX_CONCURRENCY), stop: StopHandle::default(), on_warning: None }
+    }
+
+    /// Called when a run cannot start all of its workers and carries on with fewer
+    pub fn on_warning<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_warning = Some(Arc::new(callback));
+        self
+    }
+
+    /// Handle that stops this engine and every clone of it
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.is_stopped()
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// An engine with its own worker count that stops together with this one
+    pub fn with_concurrency(&self, concurrency: usize) -> Self {
+        Engine { concurrency: concurrency.clamp(1, MAX_CONCURRENCY), ..self.clone() }
+    }
+
+    /// Run `work` over every item with at most `concurrency` calls in flight,
+    /// or until the engine is stopped
+    pub fn run<T, F>(&self, items: &[T], work: F)
+    where
+        T: Sync,
+        F: Fn(&T) + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let workers = self.concurrency.min(items.len());
+
+        thread::scope(|scope| {
+            let worker = || while !self.is_stopped() {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else { break };
+                work(item);
+            };
+
+            let mut spawned = 0;
+            for id in 0..workers {
+                let builder = thread::Builder::new()
+                    .name(format!("worker-{}", id))
+                    .stack_size(WORKER_STACK_SIZE);
+                match builder.spawn_scoped(scope, worker) {
+                    Ok(_) => spawned += 1,
+                    // Out of threads: carry on with the workers we already have
+                    Err(err) => {
+                        if let Some(callback) = &self.on_warning {
+                            callback(&format!("⚠️ Could only start {} of {} workers: {}", spawned, workers, err));
+                        }
+                        break;
+                    },
+                }
+            }
+
+            // Without any worker the calling thread does the work itself
+            if spawned == 0 {
+                worker();
+            }
+        });
+    }
+}
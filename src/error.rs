@@ -0,0 +1,65 @@
+//! Error type of the library API.
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A wordlist file does not exist
+    WordlistNotFound(String),
+    /// The custom wordlist type was chosen without a path
+    MissingCustomWordlist,
+    /// None of the SecLists directories exist
+    SecListsNotFound,
+    /// No resolvers were given and none are configured on the system
+    NoResolvers,
+    /// A scanner option has an unusable value
+    InvalidOption(String),
+    /// A checkpoint is unreadable or was taken for another domain or wordlist
+    Checkpoint(String),
+    /// The scan history is unreadable or lacks a requested scan
+    History(String),
+    /// A change event could not be delivered to one of its sinks
+    Notification(String),
+    /// Reading an input file or talking to the network failed
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::WordlistNotFound(path) => write!(f, "Wordlist not found at path: {}", path),
+            Error::MissingCustomWordlist => {
+                f.write_str("Custom wordlist path must be provided when using Custom wordlist type")
+            },
+            Error::SecListsNotFound => f.write_str(
+                "Could not find SecLists wordlist directory. \
+                Please install SecLists or provide a custom path using --seclists-path",
+            ),
+            Error::NoResolvers => f.write_str("No resolvers given and none found in the system configuration"),
+            Error::InvalidOption(message)
+            | Error::Checkpoint(message)
+            | Error::History(message)
+            | Error::Notification(message) => f.write_str(message),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
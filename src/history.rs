@@ -0,0 +1,151 @@
+//! Scan history kept in an append-only NDJSON file.
+//!
+//! Every scanned target becomes one line holding its options and host
+//! records, so later runs can be compared with [`crate::diff`]. Appending a
+//! line never rewrites earlier scans, and the file stays readable with jq.
+//!
+//! Runs sharing a history file append under an exclusive file lock and re-read
+//! the file first, so concurrent scans never hand out the same id.
+//!
+//! A line that does not parse, such as one cut short by a crash, is skipped
+//! with a warning rather than making the whole history unreadable.
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::output::TargetReport;
+use crate::results::HostRecord;
+
+/// One stored scan of one domain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanEntry {
+    /// Position in the history, starting at 1
+    pub id: u64,
+    pub domain: String,
+    pub started_at: String,
+    pub duration_secs: f64,
+    /// Command line options the scan ran with
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// The scan was stopped early, so hosts missing from it may still exist
+    #[serde(default)]
+    pub interrupted: bool,
+    pub results: Vec<HostRecord>,
+}
+
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    entries: Vec<ScanEntry>,
+    /// Lines skipped the last time the file was read
+    warnings: Vec<String>,
+}
+
+/// `$XDG_DATA_HOME/sub_crawler/history.ndjson`, falling back to `~/.local/share`
+pub fn default_path() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data_home.join("sub_crawler").join("history.ndjson"))
+}
+
+impl History {
+    /// Read the history at `path`; a missing file is an empty history
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(History { path: path.to_path_buf(), entries: Vec::new(), warnings: Vec::new() });
+            },
+            Err(err) => return Err(err.into()),
+        };
+        let (entries, warnings) = read_entries(file, path)?;
+        Ok(History { path: path.to_path_buf(), entries, warnings })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Why lines were skipped the last time the file was read
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Append the scan in `report` and return its id.
+    /// Scans other runs appended since the history was opened are read in first.
+    pub fn record(&mut self, report: &TargetReport, started_at: &str, options: &[String]) -> Result<u64> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&self.path)?;
+        // Held until `file` is dropped, so no other run appends between reading the last id and writing
+        file.lock()?;
+        file.seek(SeekFrom::Start(0))?;
+        (self.entries, self.warnings) = read_entries(&mut file, &self.path)?;
+        // A line cut short by a crash must not swallow the one appended after it
+        let mut last = [0u8; 1];
+        if file.seek(SeekFrom::End(-1)).is_ok() && file.read_exact(&mut last).is_ok() && last[0] != b'\n' {
+            file.write_all(b"\n")?;
+        }
+
+        let entry = ScanEntry {
+            id: self.entries.last().map_or(1, |entry| entry.id + 1),
+            domain: report.domain.clone(),
+            started_at: started_at.to_string(),
+            duration_secs: report.duration_secs,
+            options: options.to_vec(),
+            interrupted: report.interrupted,
+            results: report.results.clone(),
+        };
+        let mut line = serde_json::to_string(&entry).map_err(|err| Error::History(err.to_string()))?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+
+        let id = entry.id;
+        self.entries.push(entry);
+        Ok(id)
+    }
+
+    /// Scans of `domain`, oldest first
+    pub fn scans(&self, domain: &str) -> Vec<&ScanEntry> {
+        self.entries.iter().filter(|entry| entry.domain == domain).collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ScanEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Most recent scan of `domain`
+    pub fn latest(&self, domain: &str) -> Option<&ScanEntry> {
+        self.entries.iter().rev().find(|entry| entry.domain == domain)
+    }
+
+    /// Most recent scan of `domain` that ran to the end
+    pub fn latest_complete(&self, domain: &str) -> Option<&ScanEntry> {
+        self.entries.iter().rev().find(|entry| entry.domain == domain && !entry.interrupted)
+    }
+}
+
+/// Scans in `file`, and a warning for every line that could not be read as one
+fn read_entries(file: impl Read, path: &Path) -> Result<(Vec<ScanEntry>, Vec<String>)> {
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+    for (number, line) in BufReader::new(file).split(b'\n').enumerate() {
+        let line = line?;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        match serde_json::from_slice(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => warnings.push(format!("Skipping invalid scan on line {} of {}: {}", number + 1, path.display(), err)),
+        }
+    }
+    Ok((entries, warnings))
+}
@@ -0,0 +1,183 @@
+//! Minimal blocking HTTP/1.1 client for probing and fingerprinting hosts
+//! and for delivering webhook notifications.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::tls;
+
+// Bodies are only searched for fingerprints; the start of the page is enough
+const MAX_BODY: u64 = 256 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// First header named `name`, compared case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    pub tls: bool,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl Url {
+    pub fn new(tls: bool, host: &str, port: u16, path: &str) -> Self {
+        Url { tls, host: host.to_lowercase(), port, path: path.to_string() }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let (tls, rest) = if let Some(rest) = value.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            (false, value.strip_prefix("http://")?)
+        };
+        let (authority, path) = rest.find('/').map_or((rest, "/"), |at| (&rest[..at], &rest[at..]));
+        let default_port = if tls { 443 } else { 80 };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port),
+        };
+        Some(Url::new(tls, host, port, path))
+    }
+
+    /// Resolve a Location header against this URL
+    pub fn join(&self, location: &str) -> Option<Self> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            Url::parse(location)
+        } else if let Some(rest) = location.strip_prefix("//") {
+            Url::parse(&format!("{}://{}", if self.tls { "https" } else { "http" }, rest))
+        } else if location.starts_with('/') {
+            Some(Url { path: location.to_string(), ..self.clone() })
+        } else {
+            let dir = &self.path[..self.path.rfind('/').map_or(0, |at| at + 1)];
+            Some(Url { path: format!("{}{}", dir, location), ..self.clone() })
+        }
+    }
+
+    /// The response and, over TLS, the names on the server's certificate
+    pub fn fetch(&self, addr: IpAddr, timeout: Duration) -> io::Result<(HttpResponse, Vec<String>)> {
+        let addr = SocketAddr::new(addr, self.port);
+        if self.tls {
+            get_tls(addr, &self.host, &self.path, timeout)
+        } else {
+            Ok((get(addr, &self.host, &self.path, timeout)?, Vec::new()))
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (scheme, default_port) = if self.tls { ("https", 443) } else { ("http", 80) };
+        if self.port == default_port {
+            write!(f, "{}://{}{}", scheme, self.host, self.path)
+        } else {
+            write!(f, "{}://{}:{}{}", scheme, self.host, self.port, self.path)
+        }
+    }
+}
+
+/// GET `path` from `addr` over plain HTTP, sending `host` as the Host header
+pub fn get(addr: SocketAddr, host: &str, path: &str, timeout: Duration) -> io::Result<HttpResponse> {
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    request(&mut stream, host, path)
+}
+
+/// GET `path` from `addr` over HTTPS, using `host` for SNI and the Host header.
+/// Also returns the DNS names on the certificate the server presented.
+pub fn get_tls(addr: SocketAddr, host: &str, path: &str, timeout: Duration) -> io::Result<(HttpResponse, Vec<String>)> {
+    let mut stream = tls::connect(addr, host, timeout)?;
+    let names = tls::certificate_names(&stream);
+    Ok((request(&mut stream, host, path)?, names))
+}
+
+/// POST a JSON `body` to `url`, resolving its host with the system resolver.
+/// HTTPS certificates are verified, unlike the ones of probed hosts.
+pub fn post_json(url: &Url, body: &str, timeout: Duration) -> io::Result<HttpResponse> {
+    let addr = (url.host.as_str(), url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} has no address", url.host)))?;
+    let default_port = if url.tls { 443 } else { 80 };
+    let authority = if url.port == default_port { url.host.clone() } else { format!("{}:{}", url.host, url.port) };
+
+    if url.tls {
+        let mut stream = tls::connect_verified(addr, &url.host, timeout)?;
+        send(&mut stream, "POST", &authority, &url.path, Some(body))
+    } else {
+        let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        send(&mut stream, "POST", &authority, &url.path, Some(body))
+    }
+}
+
+/// Send a GET over an established connection and read the response
+pub fn request<S: Read + Write>(stream: &mut S, host: &str, path: &str) -> io::Result<HttpResponse> {
+    send(stream, "GET", host, path, None)
+}
+
+/// Send a request with an optional JSON body and read the response
+fn send<S: Read + Write>(stream: &mut S, method: &str, host: &str, path: &str, json: Option<&str>) -> io::Result<HttpResponse> {
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: sub_crawler/{}\r\nAccept: */*\r\nConnection: close\r\n",
+        method, path, host, env!("CARGO_PKG_VERSION")
+    );
+    if let Some(body) = json {
+        request.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+    request.push_str(json.unwrap_or_default());
+    stream.write_all(request.as_bytes())?;
+
+    let mut raw = Vec::new();
+    // Servers that keep the connection open despite `close` hit the read timeout,
+    // and TLS servers often skip close_notify; whatever arrived by then is used
+    let cut_short = [io::ErrorKind::WouldBlock, io::ErrorKind::TimedOut, io::ErrorKind::UnexpectedEof];
+    match stream.take(MAX_BODY).read_to_end(&mut raw) {
+        Ok(_) => {},
+        Err(err) if !raw.is_empty() && cut_short.contains(&err.kind()) => {},
+        Err(err) => return Err(err),
+    }
+    parse_response(&raw)
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<HttpResponse> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Malformed HTTP response");
+    let split = raw.windows(4).position(|window| window == b"\r\n\r\n").ok_or_else(invalid)?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut lines = head.split("\r\n");
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(invalid)?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body: String::from_utf8_lossy(&raw[split + 4..]).into_owned(),
+    })
+}
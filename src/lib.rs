@@ -0,0 +1,53 @@
+//! Subdomain enumeration as a library.
+//!
+//! The `sub_crawler` binary is a thin layer over this crate: it turns command
+//! line flags into a [`ScannerBuilder`], prints [`Event`]s as they arrive and
+//! writes the [`TargetReport`] of each target.
+//!
+//! ```no_run
+//! use sub_crawler::{Event, Scanner};
+//!
+//! let scanner = Scanner::builder()
+//!     .concurrency(100)
+//!     .on_event(|event| {
+//!         if let Event::Discovered(record) = event {
+//!             println!("{}", record.hostname);
+//!         }
+//!     })
+//!     .build()?;
+//! let report = scanner.scan("example.com")?;
+//! println!("{} subdomains", report.found);
+//! # Ok::<(), sub_crawler::Error>(())
+//! ```
+
+pub mod axfr;
+pub mod checkpoint;
+pub mod diff;
+pub mod dns;
+pub mod engine;
+mod error;
+pub mod history;
+mod http;
+pub mod monitor;
+pub mod outcome;
+pub mod output;
+pub mod passive;
+pub mod permutations;
+pub mod probe;
+pub mod ratelimit;
+pub mod recursion;
+pub mod resolvers;
+pub mod results;
+pub mod reverse;
+mod scanner;
+pub mod takeover;
+pub mod targets;
+mod tls;
+pub mod wildcard;
+pub mod wordlist;
+pub mod zonewalk;
+
+pub use error::{Error, Result};
+pub use output::TargetReport;
+pub use results::{HostRecord, Source};
+pub use scanner::{Event, Level, Scanner, ScannerBuilder};
@@ -1,54 +1,80 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::net::ToSocketAddrs;
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand};
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 
-// Default wordlist of subdomains
-const DEFAULT_WORDLIST: &[&str] = &[
-    "www", "mail", "remote", "blog", "webmail", "server", "ns1", "ns2",
-    "smtp", "secure", "vpn", "m", "shop", "ftp", "mail2", "test", "portal",
-    "ns", "ww1", "host", "support", "dev", "web", "bbs", "ww42", "mx", "email",
-    "cloud", "1", "2", "forum", "admin", "api", "cdn", "stage", "gw", "dns",
-    "download", "demo", "dashboard", "app", "beta", "auth", "cms", "testing"
-];
-
-// Potential SecLists wordlist locations
-const POTENTIAL_WORDLIST_PATHS: &[&str] = &[
-    "/usr/share/wordlists/seclists/Discovery/DNS/",
-    "/usr/share/seclists/Discovery/DNS/",
-    "/opt/seclists/Discovery/DNS/",
-    "/usr/local/share/seclists/Discovery/DNS/"
-];
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
-enum WordlistType {
-    /// Light wordlist with 45 predefined subdomains
-    Light,
-    /// Top 5000 subdomains from SecLists
-    Top5000,
-    /// Top 20000 subdomains from SecLists
-    Top20000,
-    /// Top 110000 subdomains from SecLists
-    Top110000,
-    /// Custom wordlist file
-    Custom,
+use sub_crawler::diff::ScanDiff;
+use sub_crawler::dns::{self, RecordType};
+use sub_crawler::engine;
+use sub_crawler::history::{self, History};
+use sub_crawler::monitor::{ChangeEvent, Notifier, Sink};
+use sub_crawler::outcome::Outcome;
+use sub_crawler::output::{self, OutputFormat, OutputWriter, ScanMetadata, TargetReport};
+use sub_crawler::passive::PassiveDataset;
+use sub_crawler::permutations::{self, Permutator};
+use sub_crawler::probe::{ProbePort, Prober};
+use sub_crawler::recursion::RecursionPolicy;
+use sub_crawler::resolvers::{self, ResolverPool};
+use sub_crawler::results::{self, HostRecord};
+use sub_crawler::takeover::FingerprintDb;
+use sub_crawler::targets;
+use sub_crawler::wordlist::{self, WordlistType};
+use sub_crawler::{Error, Event, Level, Scanner};
+
+// Where decoration goes: stdout by default, stderr when stdout carries
+// machine-readable results, and nowhere at all with --silent
+const STATUS_STDOUT: u8 = 0;
+const STATUS_STDERR: u8 = 1;
+const STATUS_SILENT: u8 = 2;
+static STATUS_MODE: AtomicU8 = AtomicU8::new(STATUS_STDOUT);
+
+/// println! for banners, progress notes and the human-readable report
+macro_rules! status {
+    ($($arg:tt)*) => {
+        match STATUS_MODE.load(Ordering::Relaxed) {
+            STATUS_STDOUT => println!($($arg)*),
+            STATUS_STDERR => eprintln!($($arg)*),
+            _ => {},
+        }
+    };
 }
 
 /// Subdomain Reconnaissance Tool
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Target domain to scan
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    /// Scan history file (defaults to ~/.local/share/sub_crawler/history.ndjson)
+    #[arg(long, env = "SUB_CRAWLER_HISTORY", global = true)]
+    history_file: Option<String>,
+
+    /// Don't store this run in the scan history
+    #[arg(long)]
+    no_history: bool,
+}
+
+/// Targets and options of a scan, shared by plain runs and monitor rounds
+#[derive(clap::Args, Debug)]
+struct ScanArgs {
+    /// Target domains to scan; use - to read them from stdin
     #[arg(index(1))]
-    domain: String,
+    domains: Vec<String>,
+
+    /// File with one target domain per line
+    #[arg(short = 'd', long)]
+    domain_list: Option<String>,
 
     /// Wordlist type to use
     #[arg(short, long, value_enum, default_value_t = WordlistType::Light)]
@@ -59,33 +85,204 @@ struct Args {
     seclists_path: Option<String>,
 
     /// Path to custom wordlist file (used only when wordlist type is Custom)
-    #[arg(short, long, requires = "custom")]
+    #[arg(short, long)]
     custom_wordlist: Option<String>,
 
-    /// Number of threads to use
-    #[arg(short, long, default_value_t = 10)]
-    threads: usize,
+    /// Brute-force discovered hosts again as new base domains
+    #[arg(long)]
+    recursive: bool,
+
+    /// Maximum subdomain levels below the target when recursing
+    #[arg(long, default_value_t = 2, requires = "recursive")]
+    depth: usize,
+
+    /// Smaller wordlist for the recursive levels (defaults to the main wordlist)
+    #[arg(long, requires = "recursive")]
+    recursive_wordlist: Option<String>,
+
+    /// Only recurse into hosts whose first label contains one of these, comma separated
+    #[arg(long, value_delimiter = ',', requires = "recursive")]
+    recurse_filter: Vec<String>,
+
+    /// Maximum number of hosts brute-forced as new bases across the whole run
+    #[arg(long, default_value_t = 100, requires = "recursive")]
+    recursion_limit: usize,
+
+    /// Skip the zone transfer attempt against the target's nameservers
+    #[arg(long)]
+    no_axfr: bool,
+
+    /// Port to use for zone transfers
+    #[arg(long, default_value_t = 53)]
+    axfr_port: u16,
+
+    /// Skip NSEC/NSEC3 zone walking of DNSSEC-signed targets
+    #[arg(long)]
+    no_zone_walk: bool,
+
+    /// Skip subdomain takeover checks of CNAME targets
+    #[arg(long)]
+    no_takeover: bool,
+
+    /// JSON takeover fingerprint database to use instead of the built-in one
+    #[arg(long)]
+    fingerprints: Option<String>,
+
+    /// Probe discovered hosts over HTTP and HTTPS
+    #[arg(long)]
+    probe: bool,
+
+    /// Ports to probe, comma separated; 443, 8443 and 9443 use TLS unless prefixed with http: or https: (e.g. https:4443)
+    #[arg(long, value_delimiter = ',', default_value = "80,443", requires = "probe", value_parser = ProbePort::from_str)]
+    probe_ports: Vec<ProbePort>,
+
+    /// Maximum number of hosts probed at once
+    #[arg(long, default_value_t = 50, requires = "probe")]
+    probe_concurrency: usize,
+
+    /// Seconds to wait for a probe connection or response
+    #[arg(long, default_value_t = 5, requires = "probe")]
+    probe_timeout: u64,
+
+    /// Passive dataset to merge in: crt.sh JSON, NDJSON, CSV or a host list; directories are read recursively; may be repeated
+    #[arg(long = "import", value_name = "PATH")]
+    imports: Vec<String>,
+
+    /// Resolve imported names and keep only the ones still live
+    #[arg(long, requires = "imports")]
+    resolve_imported: bool,
+
+    /// Sweep PTR records across the netblocks of discovered IPv4 addresses
+    #[arg(long)]
+    reverse: bool,
+
+    /// Prefix length of the swept netblocks
+    #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u8).range(16..=32), requires = "reverse")]
+    reverse_prefix: u8,
+
+    /// Skip resolving names found on the TLS certificates of probed hosts
+    #[arg(long, requires = "probe")]
+    no_tls_san: bool,
+
+    /// Periodically save the progress of the wordlist scan to this file
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<String>,
+
+    /// Continue the scan saved in --checkpoint instead of starting over
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Resolve alterations of the discovered names in a second pass
+    #[arg(long)]
+    permutations: bool,
+
+    /// File with words to mix into discovered names (defaults to a built-in list)
+    #[arg(long, requires = "permutations")]
+    permutation_words: Option<String>,
+
+    /// Maximum number of permutations resolved per target
+    #[arg(long, default_value_t = 20_000, requires = "permutations")]
+    max_permutations: usize,
+
+    /// Maximum number of lookups in flight at once
+    #[arg(short = 't', long, visible_alias = "threads", default_value_t = 200)]
+    concurrency: usize,
+
+    /// Seconds to wait for a DNS response before retrying
+    #[arg(long, default_value_t = 2)]
+    timeout: u64,
+
+    /// Number of times a failed DNS query is retried
+    #[arg(long, default_value_t = 2)]
+    retries: usize,
+
+    /// File with one resolver address per line
+    #[arg(long)]
+    resolvers: Option<String>,
+
+    /// Resolver address to use (ip or ip:port); may be repeated
+    #[arg(short = 'r', long = "resolver")]
+    resolver: Vec<String>,
+
+    /// Record types to query for each candidate, comma separated (a,aaaa,cname,mx,txt,ns,soa,srv,caa)
+    #[arg(long, value_delimiter = ',', default_value = "a,aaaa", value_parser = RecordType::from_str)]
+    record_types: Vec<RecordType>,
+
+    /// Maximum DNS queries per second across all resolvers
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Maximum DNS queries per second sent to any single resolver
+    #[arg(long)]
+    resolver_rate: Option<f64>,
+
+    /// Format of the results written to stdout or --output
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Write results to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Print only discovered hostnames, one per line, with no banner or progress bar
+    #[arg(short, long)]
+    silent: bool,
 }
 
-/// Find the first existing SecLists wordlist directory
-fn find_seclists_path(custom_path: &Option<String>) -> Option<PathBuf> {
-    // First, check if a custom path was provided
-    if let Some(path) = custom_path {
-        let custom_pathbuf = PathBuf::from(path);
-        if custom_pathbuf.exists() {
-            return Some(custom_pathbuf);
-        }
-    }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show hosts that appeared, disappeared or changed between two stored scans of a domain
+    Diff(DiffArgs),
+    /// Rescan domains on a schedule and report new or changed subdomains as JSON events
+    Monitor(Box<MonitorArgs>),
+}
 
-    // If no custom path or custom path doesn't exist, try predefined paths
-    for potential_path in POTENTIAL_WORDLIST_PATHS {
-        let path = Path::new(potential_path);
-        if path.exists() {
-            return Some(path.to_path_buf());
-        }
-    }
+#[derive(clap::Args, Debug)]
+struct MonitorArgs {
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    /// Time between rounds: seconds, or a number followed by s, m, h or d
+    #[arg(long, default_value = "24h", value_parser = parse_interval)]
+    interval: Duration,
 
-    None
+    /// Also append every event to this file as NDJSON
+    #[arg(long, value_name = "FILE")]
+    events_file: Option<String>,
+
+    /// Also POST every event as JSON to this http:// or https:// URL
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// Seconds to wait for the webhook to accept an event
+    #[arg(long, default_value_t = 10, requires = "webhook")]
+    webhook_timeout: u64,
+
+    /// Run a single round and exit, for use from cron
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Domain whose scans are compared
+    domain: String,
+
+    /// Id of the older scan (defaults to the one before --to)
+    #[arg(long)]
+    from: Option<u64>,
+
+    /// Id of the newer scan (defaults to the latest)
+    #[arg(long)]
+    to: Option<u64>,
+
+    /// List the stored scans of the domain instead
+    #[arg(long, conflicts_with_all = ["from", "to"])]
+    list: bool,
+
+    /// Print the differences as JSON
+    #[arg(long)]
+    json: bool,
 }
 
 fn print_banner() {
@@ -97,199 +294,733 @@ fn print_banner() {
 ███████║╚██████╔╝██████╔╝     ╚██████╗██║  ██║██║  ██║╚███╔███╔╝███████╗███████╗██║  ██║
 ╚══════╝ ╚═════╝ ╚═════╝       ╚═════╝╚═╝  ╚═╝╚═╝  ╚═╝ ╚══╝╚══╝ ╚══════╝╚══════╝╚═╝  ╚═╝
 "#;
-    println!("{}", banner.blue());
-    println!("{}", "                 [ By Sylar ]".red());
-    println!("{}", "         🔍 Subdomain Reconnaissance Tool 🎯".green());
-    println!("============================================================================================");
+    status!("{}", banner.blue());
+    status!("{}", "                 [ By Sylar ]".red());
+    status!("{}", "         🔍 Subdomain Reconnaissance Tool 🎯".green());
+    status!("============================================================================================");
 }
 
 
-fn load_wordlist(
-    wordlist_type: &WordlistType,
-    custom_path: &Option<String>,
-    seclists_path: &Option<String>
-) -> Result<Vec<String>, std::io::Error> {
-    match wordlist_type {
-        WordlistType::Light => {
-            Ok(DEFAULT_WORDLIST.iter().map(|&s| s.to_string()).collect())
-        },
-        WordlistType::Top5000 |
-        WordlistType::Top20000 |
-        WordlistType::Top110000 => {
-            // Determine the filename based on wordlist type
-            let filename = match wordlist_type {
-                WordlistType::Top5000 => "subdomains-top1million-5000.txt",
-                WordlistType::Top20000 => "subdomains-top1million-20000.txt",
-                WordlistType::Top110000 => "subdomains-top1million-110000.txt",
-                _ => unreachable!()
-            };
+/// Build the resolver list from --resolvers / -r, falling back to the system configuration
+fn collect_resolvers(
+    resolvers_file: &Option<String>,
+    resolver_args: &[String]
+) -> Result<Vec<SocketAddr>, std::io::Error> {
+    let mut addrs = match resolvers_file {
+        Some(path) => resolvers::load_resolvers_from_file(path)?,
+        None => Vec::new(),
+    };
+    for value in resolver_args {
+        addrs.push(resolvers::parse_resolver(value)?);
+    }
 
-            // Find a valid SecLists path
-            let seclists_base_path = find_seclists_path(seclists_path)
-                .ok_or_else(|| std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Could not find SecLists wordlist directory. \
-                    Please install SecLists or provide a custom path using --seclists-path"
-                ))?;
+    // Drop duplicates while keeping the order the user gave
+    let mut seen = HashSet::new();
+    addrs.retain(|addr| seen.insert(*addr));
 
-            let wordlist_path = seclists_base_path.join(filename);
+    if addrs.is_empty() {
+        addrs = dns::system_nameservers();
+    }
+    Ok(addrs)
+}
 
-            // Load the wordlist
-            load_wordlist_from_file(&wordlist_path.to_string_lossy())
-        },
-        WordlistType::Custom => {
-            match custom_path {
-                Some(file_path) => load_wordlist_from_file(file_path),
-                None => Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "Custom wordlist path must be provided when using Custom wordlist type"
-                ))
-            }
+fn print_resolver_health(pool: &ResolverPool) {
+    status!("{}", "Resolver Health:".cyan());
+    for stats in pool.stats() {
+        let line = format!(
+            "  └─ {:<22} queries: {:<7} ok: {:<7} timeouts: {:<5} errors: {:<5} avg: {:>4}ms  benched: {}x",
+            stats.addr.to_string(),
+            stats.queries,
+            stats.successes,
+            stats.timeouts,
+            stats.errors,
+            stats.avg_latency.as_millis(),
+            stats.times_benched,
+        );
+        if stats.benched || stats.error_rate() > 0.25 {
+            status!("{}", line.red());
+        } else {
+            status!("{}", line.blue());
         }
     }
 }
 
-// Helper function to load wordlist from a file
-fn load_wordlist_from_file(file_path: &str) -> Result<Vec<String>, std::io::Error> {
-    let path = Path::new(file_path);
-    if !path.exists() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("Wordlist not found at path: {}", file_path)
-        ));
+fn print_wildcard_summary(report: &TargetReport) {
+    let detected = &report.wildcard_fingerprints;
+    if detected.is_empty() {
+        status!("{}", "Wildcard DNS: none detected".green());
+        return;
     }
 
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let wordlist: Vec<String> = reader
-        .lines()
-        .filter_map(Result::ok)
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-
-    Ok(wordlist)
+    status!("{}", "Wildcard DNS detected:".yellow());
+    for (level, fingerprint) in detected {
+        let mut answers: Vec<String> = fingerprint.addresses.iter().map(|ip| ip.to_string()).collect();
+        answers.extend(fingerprint.cnames.iter().map(|cname| format!("CNAME {}", cname)));
+        status!("{}", format!("  └─ *.{} -> {} (ttl {})",
+            level, answers.join(", "), fingerprint.max_ttl
+        ).yellow());
+    }
+    status!("{}", format!("  └─ Filtered {} wildcard hits", report.wildcard_filtered).yellow());
 }
 
-// ... [rest of the previous implementation remains the same]
-fn check_subdomain(subdomain: &str, domain: &str) -> Option<String> {
-    let hostname = format!("{}.{}", subdomain, domain);
+fn print_outcome_summary(outcomes: &BTreeMap<String, u64>) {
+    let count = |outcome: Outcome| outcomes.get(&outcome.to_string()).copied().unwrap_or(0);
+    let total: u64 = Outcome::ALL.iter().map(|&outcome| count(outcome)).sum();
+    let conclusive: u64 = Outcome::ALL.iter().filter(|outcome| !outcome.is_transient()).map(|&outcome| count(outcome)).sum();
+    status!("{}", "Lookup Outcomes:".cyan());
+    for outcome in Outcome::ALL {
+        let line = format!("  └─ {:<14} {}", outcome.to_string(), count(outcome));
+        if outcome.is_transient() && count(outcome) > 0 {
+            status!("{}", line.red());
+        } else {
+            status!("{}", line.blue());
+        }
+    }
 
-    // Attempt to resolve the hostname
-    match format!("{}:80", hostname).to_socket_addrs() {
-        Ok(_) => Some(hostname),
-        Err(_) => None
+    let coverage = if total == 0 { 100.0 } else { conclusive as f64 * 100.0 / total as f64 };
+    let line = format!("  └─ Coverage: {}/{} candidates answered conclusively ({:.2}%)",
+        conclusive, total, coverage
+    );
+    if conclusive < total {
+        status!("{}", line.yellow());
+    } else {
+        status!("{}", line.green());
     }
 }
 
-fn scan_subdomains(domain: &str, wordlist: &[String], max_threads: usize) -> Vec<String> {
-    // Enhanced thread logging
-    let actual_threads = std::cmp::min(max_threads, wordlist.len());
-    println!("{}", format!("🧵 Active Threads: {}", actual_threads).blue());
-    println!("{}", format!("🧩 Chunk Size: {} entries per thread",
-        (wordlist.len() + actual_threads - 1) / actual_threads
-    ).blue());
-
-
-    let found_domains = Arc::new(Mutex::new(HashSet::new()));
-    let progress_bar = ProgressBar::new(wordlist.len() as u64);
+/// Progress bar for a pass over `len` lookups, hidden with --silent
+fn progress_bar(len: u64) -> ProgressBar {
+    let progress_bar = if STATUS_MODE.load(Ordering::Relaxed) == STATUS_SILENT {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(len)
+    };
     progress_bar.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}")
             .expect("Invalid progress bar template")
             .progress_chars("#>-")
     );
+    progress_bar
+}
 
-    let mut handles = vec![];
+/// Print scanner events and stream discoveries to the output
+fn handle_event(event: &Event, output: &OutputWriter, pass: &Mutex<Option<ProgressBar>>) {
+    match event {
+        Event::Discovered(record) => {
+            if let Err(err) = output.discovered(record) {
+                eprintln!("Failed to write result for {}: {}", record.hostname, err);
+            }
+        },
+        Event::Notice(level, message) => match level {
+            Level::Info => status!("{}", message.green()),
+            Level::Detail => status!("{}", format!("  └─ {}", message).blue()),
+            Level::Warning => status!("{}", message.yellow()),
+            Level::Finding => status!("{}", message.red()),
+        },
+        Event::PassStarted { total, workers } => {
+            status!("{}", format!("🧵 Concurrent Lookups: {}", workers).blue());
+            *pass.lock().unwrap() = Some(progress_bar(*total));
+        },
+        Event::Progress { rate } => {
+            if let Some(progress_bar) = pass.lock().unwrap().as_ref() {
+                progress_bar.set_message(rate.to_string());
+                progress_bar.inc(1);
+            }
+        },
+        Event::PassFinished => {
+            if let Some(progress_bar) = pass.lock().unwrap().take() {
+                progress_bar.finish_with_message("Scan complete!");
+            }
+        },
+    }
+}
 
-    // Split wordlist into chunks for threading
-    let chunk_size = (wordlist.len() + max_threads - 1) / max_threads;
+/// Print the results and summaries of one target
+fn print_report(report: &TargetReport) {
+    status!("\n==================================================");
+    status!("{}", format!("Scan Results: {}", report.domain).cyan());
+    if report.interrupted {
+        status!("{}", format!("Scan interrupted after {:.2} seconds, results are partial", report.duration_secs).yellow());
+    } else {
+        status!("{}", format!("Scan completed in {:.2} seconds", report.duration_secs).green());
+    }
+    status!("{}", format!("Found {} subdomains:", report.found).green());
 
-    for chunk in wordlist.chunks(chunk_size) {
-        let chunk = chunk.to_vec();
-        let domain = domain.to_string();
-        let found_domains = Arc::clone(&found_domains);
-        let progress_bar = progress_bar.clone();
+    for record in &report.results {
+        status!("{}", format!("  └─ {}", output::text_line(record)).magenta());
+        for (rtype, values) in &record.records {
+            for value in values {
+                status!("{}", format!("       {:<6} {}", rtype, value).blue());
+            }
+        }
+        for probe in &record.http {
+            status!("{}", format!("       {}", output::probe_line(probe)).green());
+        }
+        let resolver = record.resolver.map_or_else(|| "-".to_string(), |addr| addr.to_string());
+        status!("{}", format!("       via {} at {}",
+            resolver, results::format_timestamp(record.timestamp)
+        ).dimmed());
+    }
 
-        let handle = thread::spawn(move || {
-            for subdomain in chunk {
-                if let Some(discovered_domain) = check_subdomain(&subdomain, &domain) {
-                    let mut domains = found_domains.lock().unwrap();
-                    domains.insert(discovered_domain);
-                }
-                progress_bar.inc(1);
+    if !report.apex_records.is_empty() {
+        status!();
+        status!("{}", format!("Apex Records for {}:", report.domain).cyan());
+        for (rtype, values) in &report.apex_records {
+            for value in values {
+                status!("{}", format!("  └─ {:<6} {}", rtype, value).blue());
             }
-        });
+        }
+    }
 
-        handles.push(handle);
+    let mut takeovers: Vec<&HostRecord> = report.results.iter().filter(|record| record.takeover.is_some()).collect();
+    if !takeovers.is_empty() {
+        takeovers.sort_by_key(|record| std::cmp::Reverse(record.takeover.as_ref().map(|takeover| takeover.severity)));
+        status!();
+        status!("{}", "Potential Takeovers:".red());
+        for record in takeovers {
+            if let Some(takeover) = &record.takeover {
+                status!("{}", format!("  └─ [{}] {} -> {} ({})",
+                    takeover.severity,
+                    record.hostname,
+                    takeover.cname,
+                    takeover.service.as_deref().unwrap_or("unknown service")
+                ).red());
+            }
+        }
     }
 
-    // Improved thread joining with error handling
-    for (i, handle) in handles.into_iter().enumerate() {
-        if let Err(_) = handle.join() {
-            eprintln!("Thread {} failed to complete", i);
+    if !report.zone_transfers.is_empty() {
+        status!();
+        status!("{}", "Open Zone Transfer:".red());
+        for server in &report.zone_transfers {
+            status!("{}", format!("  └─ {} allowed AXFR of {}", server, report.domain).red());
         }
     }
 
-    progress_bar.finish_with_message("Scan complete!");
+    status!();
+    print_outcome_summary(&report.outcomes);
+    status!();
+    print_wildcard_summary(report);
+}
 
-    // Convert Arc<Mutex<HashSet>> to sorted Vec
-    let mut results: Vec<String> = found_domains.lock().unwrap().iter().cloned().collect();
-    results.sort();
-    results
+/// `90`, `90s`, `30m`, `6h` or `1d`
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = value.find(|c: char| !c.is_ascii_digit()).map_or((value, ""), |at| value.split_at(at));
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return Err(format!("unknown unit '{}', use s, m, h or d", unit)),
+    };
+    match number.parse::<u64>() {
+        Ok(count) if count > 0 => Ok(Duration::from_secs(count.saturating_mul(seconds))),
+        _ => Err("expected a positive number".to_string()),
+    }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    print_banner();
+fn print_diff(diff: &ScanDiff) {
+    println!("{}", format!("Changes in {} from scan #{} to scan #{}:", diff.domain, diff.from, diff.to).cyan());
+    if diff.partial {
+        println!("{}", "⚠️ One of the scans was interrupted, disappeared hosts may just not have been reached".yellow());
+    }
+    for record in &diff.appeared {
+        println!("{}", format!("  + {}", output::text_line(record)).green());
+    }
+    for record in &diff.disappeared {
+        println!("{}", format!("  - {}", output::text_line(record)).red());
+    }
+    for change in &diff.changed {
+        println!("{}", format!("  ~ {}", change.hostname).yellow());
+        for address in &change.added_addresses {
+            println!("{}", format!("       + {}", address).green());
+        }
+        for address in &change.removed_addresses {
+            println!("{}", format!("       - {}", address).red());
+        }
+        if let Some(cnames) = &change.cnames {
+            let chain = |cnames: &[String]| if cnames.is_empty() { "none".to_string() } else { cnames.join(" -> ") };
+            println!("{}", format!("       CNAME {} => {}", chain(&cnames.before), chain(&cnames.after)).blue());
+        }
+        for (rtype, records) in &change.records {
+            for value in &records.added {
+                println!("{}", format!("       + {} {}", rtype, value).green());
+            }
+            for value in &records.removed {
+                println!("{}", format!("       - {} {}", rtype, value).red());
+            }
+        }
+    }
+    println!("{}", format!("{} appeared, {} disappeared, {} changed",
+        diff.appeared.len(), diff.disappeared.len(), diff.changed.len()
+    ).green());
+}
+
+/// Open the history at `path`, warning about any scans in it that could not be read
+fn open_history(path: &Path) -> Result<History, Error> {
+    let history = History::open(path)?;
+    for warning in history.warnings() {
+        eprintln!("⚠️ {} {}", "Warning:".yellow(), warning);
+    }
+    Ok(history)
+}
 
+fn run_diff(args: &DiffArgs, path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.ok_or_else(|| Error::History("No history file: pass --history-file or set $HOME".to_string()))?;
+    let history = open_history(&path)?;
+    let domain = targets::normalize_domain(&args.domain).unwrap_or_default();
+    let scans = history.scans(&domain);
+
+    if args.list {
+        println!("{}", format!("Scans of {} in {}:", domain, path.display()).cyan());
+        for scan in &scans {
+            let interrupted = if scan.interrupted { " (interrupted)" } else { "" };
+            println!("{}", format!("  └─ #{:<5} {}  {:>5} hosts  {:.2}s{}",
+                scan.id, scan.started_at, scan.results.len(), scan.duration_secs, interrupted
+            ).blue());
+        }
+        return Ok(());
+    }
+
+    let find = |id: u64| {
+        history
+            .get(id)
+            .filter(|scan| scan.domain == domain)
+            .ok_or_else(|| Error::History(format!("No scan #{} of {} in the history", id, domain)))
+    };
+    let to = match args.to {
+        Some(id) => find(id)?,
+        None => *scans.last().ok_or_else(|| Error::History(format!("No scans of {} in the history", domain)))?,
+    };
+    let from = match args.from {
+        Some(id) => find(id)?,
+        None => *scans
+            .iter()
+            .rev()
+            .find(|scan| scan.id < to.id)
+            .ok_or_else(|| Error::History(format!("No scan of {} before #{} to compare with", domain, to.id)))?,
+    };
+
+    let diff = ScanDiff::between(from, to);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print_diff(&diff);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
     let args = Args::parse();
+    let history_path = args.history_file.as_ref().map(PathBuf::from).or_else(history::default_path);
+    match &args.command {
+        Some(Command::Diff(diff_args)) => run_diff(diff_args, history_path),
+        Some(Command::Monitor(monitor_args)) => run_monitor(monitor_args, history_path),
+        None => run_scan(&args.scan, if args.no_history { None } else { history_path }),
+    }
+}
 
-    // Validate thread count
-    let thread_count = match args.threads {
-        0 => {
-            eprintln!("⚠️ {} Thread count cannot be zero. Defaulting to 1.", "Warning:".yellow());
-            1
-        },
-        threads if threads > 64 => {
-            eprintln!("⚠️ {} Maximum thread count is 64. Capping at 64.", "Warning:".yellow());
-            64
-        },
-        threads => threads
+/// What a run needs once the configuration has been printed
+struct Session {
+    targets: Vec<String>,
+    scanner: Scanner,
+    output: Arc<OutputWriter>,
+}
+
+/// Everything before the first target is scanned: output, scanner, Ctrl-C handling and the
+/// configuration summary. `monitor` keeps stdout free for change events and rules out checkpoints.
+fn setup(args: &ScanArgs, monitor: bool) -> Result<Session, Box<dyn std::error::Error>> {
+    // Each round would delete the checkpoint the next one is told to resume
+    if monitor && args.checkpoint.is_some() {
+        return Err(Error::InvalidOption("--checkpoint cannot be used with monitor".to_string()).into());
+    }
+    let output = Arc::new(OutputWriter::new(
+        args.output_format,
+        args.output.as_deref(),
+        args.silent && !monitor,
+    )?);
+    if monitor && output.owns_stdout() {
+        return Err(Error::InvalidOption("Monitor events go to stdout; write results to a file with --output".to_string()).into());
+    }
+    let status_mode = match (args.silent, output.owns_stdout() || monitor) {
+        (true, _) => STATUS_SILENT,
+        (false, true) => STATUS_STDERR,
+        (false, false) => STATUS_STDOUT,
     };
+    STATUS_MODE.store(status_mode, Ordering::Relaxed);
+
+    print_banner();
+
+    // Validate concurrency
+    if !args.silent {
+        if args.concurrency == 0 {
+            eprintln!("⚠️ {} Concurrency cannot be zero. Defaulting to 1.", "Warning:".yellow());
+        } else if args.concurrency > engine::MAX_CONCURRENCY {
+            eprintln!("⚠️ {} Maximum concurrency is {}. Capping at {}.",
+                "Warning:".yellow(), engine::MAX_CONCURRENCY, engine::MAX_CONCURRENCY);
+        }
+    }
+
+    let targets = targets::collect_targets(&args.domains, &args.domain_list)?;
+    if args.checkpoint.is_some() && targets.len() > 1 {
+        return Err(Error::InvalidOption("--checkpoint supports a single target".to_string()).into());
+    }
+
+    // Load the wordlist once; every target shares it
+    let wordlist = wordlist::load_wordlist(&args.wordlist, &args.custom_wordlist, &args.seclists_path)?;
+
+    let mut builder = Scanner::builder()
+        .resolvers(collect_resolvers(&args.resolvers, &args.resolver)?)
+        .concurrency(args.concurrency)
+        .timeout(Duration::from_secs(args.timeout))
+        .retries(args.retries)
+        .rate(args.rate)
+        .resolver_rate(args.resolver_rate)
+        .record_types(&args.record_types)
+        .wordlist(wordlist)
+        .zone_transfer((!args.no_axfr).then_some(args.axfr_port))
+        .zone_walk(!args.no_zone_walk)
+        .tls_san(!args.no_tls_san);
+
+    let recursion = args.recursive.then(|| RecursionPolicy::new(args.depth, &args.recurse_filter, args.recursion_limit));
+    let recursive_wordlist = match &args.recursive_wordlist {
+        Some(path) => Some(wordlist::load_wordlist_from_file(path)?),
+        None => None,
+    };
+
+    let permutator = if args.permutations {
+        let words: Vec<String> = match &args.permutation_words {
+            Some(path) => wordlist::load_wordlist_from_file(path)?
+                .into_iter()
+                .map(|word| word.trim().to_lowercase())
+                .collect(),
+            None => permutations::DEFAULT_WORDS.iter().map(|word| word.to_string()).collect(),
+        };
+        Some((words.len(), Permutator::new(words, args.max_permutations)))
+    } else {
+        None
+    };
+
+    let fingerprints = match (&args.fingerprints, args.no_takeover) {
+        (_, true) => None,
+        (Some(path), false) => Some(FingerprintDb::load(path)?),
+        (None, false) => Some(FingerprintDb::builtin()),
+    };
+    let fingerprint_count = fingerprints.as_ref().map(FingerprintDb::len);
+    builder = builder.takeover(fingerprints);
+
+    if args.probe {
+        builder = builder.probe(Prober::new(
+            args.probe_ports.clone(),
+            args.probe_concurrency,
+            Duration::from_secs(args.probe_timeout.max(1)),
+        ));
+    }
+    if args.reverse {
+        builder = builder.reverse(args.reverse_prefix);
+    }
+
+    let passive = if args.imports.is_empty() {
+        None
+    } else {
+        Some(PassiveDataset::load(&args.imports)?)
+    };
+    let passive_counts = passive.as_ref().map(|dataset| (dataset.len(), dataset.files));
+    if let Some(dataset) = passive {
+        builder = builder.passive(dataset, args.resolve_imported);
+    }
+
+    let recursion_limit = recursion.as_ref().map(|policy| (policy.max_depth(), policy.remaining()));
+    if let Some(policy) = recursion {
+        builder = builder.recursion(policy);
+    }
+    let recursive_wordlist_len = recursive_wordlist.as_ref().map(Vec::len);
+    if let Some(words) = recursive_wordlist {
+        builder = builder.recursive_wordlist(words);
+    }
+    let permutation_words = permutator.as_ref().map(|(words, _)| *words);
+    if let Some((_, permutator)) = permutator {
+        builder = builder.permutations(permutator);
+    }
+
+    if let Some(path) = &args.checkpoint {
+        builder = builder.checkpoint(PathBuf::from(path), args.resume);
+    }
+
+    let events = Arc::clone(&output);
+    let pass = Mutex::new(None);
+    let scanner = builder
+        .on_event(move |event| handle_event(event, &events, &pass))
+        .build()?;
+
+    // The first Ctrl-C stops the scan and keeps what it found; a second one exits right away
+    let stop = scanner.stop_handle();
+    ctrlc::set_handler(move || {
+        if stop.is_stopped() {
+            std::process::exit(130);
+        }
+        stop.stop();
+        status!("\n{}", "Interrupted, waiting for lookups in flight (Ctrl-C again to quit now)...".yellow());
+    })?;
 
-    // Load wordlist based on user selection
-    let wordlist = load_wordlist(&args.wordlist, &args.custom_wordlist, &args.seclists_path)?;
+    if targets.len() == 1 {
+        status!("{}", format!("Target Domain: {}", targets[0]).yellow());
+    } else {
+        status!("{}", format!("Target Domains: {}", targets.len()).yellow());
+    }
+    status!("{}", format!("Wordlist Type: {:?}", args.wordlist).yellow());
+    status!("{}", format!("Wordlist Size: {} entries", scanner.wordlist().len()).yellow());
 
-    println!("{}", format!("Target Domain: {}", args.domain).yellow());
-    println!("{}", format!("Wordlist Type: {:?}", args.wordlist).yellow());
-    println!("{}", format!("Wordlist Size: {} entries", wordlist.len()).yellow());
+    status!("{}", "Engine Configuration:".yellow());
+    status!("{}", format!("  └─ Max Concurrent Lookups: {}", scanner.engine().concurrency()).blue());
 
-    // Add detailed thread information
-    println!("{}", format!("Thread Configuration:").yellow());
-    println!("{}", format!("  └─ Total Threads: {}", thread_count).blue());
-    println!("{}", format!("  └─ Expected Chunks: {}",
-        (wordlist.len() + thread_count - 1) / thread_count
+    let pool = scanner.client().pool();
+    status!("{}", "Resolver Configuration:".yellow());
+    status!("{}", format!("  └─ Resolvers: {}", pool.len()).blue());
+    for resolver in pool.resolvers() {
+        status!("{}", format!("  └─ Nameserver: {}", resolver.addr).blue());
+    }
+    status!("{}", "Rate Limiting:".yellow());
+    status!("{}", format!("  └─ Global: {}",
+        args.rate.map_or_else(|| "unlimited".to_string(), |qps| format!("{} qps", qps))
+    ).blue());
+    status!("{}", format!("  └─ Per Resolver: {}",
+        args.resolver_rate.map_or_else(|| "unlimited".to_string(), |qps| format!("{} qps", qps))
     ).blue());
 
-    println!("{}", "Starting scan...".green());
+    status!("{}", format!("Record Types: {}",
+        scanner.record_types().iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+    ).yellow());
+
+    if let Some((depth, limit)) = recursion_limit {
+        status!("{}", "Recursion:".yellow());
+        status!("{}", format!("  └─ Depth: {} levels below each target", depth).blue());
+        status!("{}", format!("  └─ Limit: {} hosts across the run", limit).blue());
+        if let Some(words) = recursive_wordlist_len {
+            status!("{}", format!("  └─ Wordlist: {} entries", words).blue());
+        }
+        if !args.recurse_filter.is_empty() {
+            status!("{}", format!("  └─ Filter: {}", args.recurse_filter.join(", ")).blue());
+        }
+    }
 
-    // Start timing
+    if let Some(words) = permutation_words {
+        status!("{}", "Permutations:".yellow());
+        status!("{}", format!("  └─ Words: {}", words).blue());
+        status!("{}", format!("  └─ Limit: {} candidates per target", args.max_permutations).blue());
+    }
+
+    if let Some(services) = fingerprint_count {
+        status!("{}", format!("Takeover Fingerprints: {} services", services).yellow());
+    }
+
+    if args.probe {
+        status!("{}", "HTTP Probing:".yellow());
+        status!("{}", format!("  └─ Ports: {}",
+            args.probe_ports.iter().map(|port| port.to_string()).collect::<Vec<_>>().join(", ")
+        ).blue());
+        status!("{}", format!("  └─ Concurrency: {}, timeout: {}s", args.probe_concurrency, args.probe_timeout).blue());
+    }
+
+    if let Some((names, files)) = passive_counts {
+        status!("{}", format!("Passive Dataset: {} names from {} files", names, files).yellow());
+    }
+
+    if let Some(path) = &args.checkpoint {
+        let mode = if args.resume { "resuming" } else { "new" };
+        status!("{}", format!("Checkpoint: {} ({})", path, mode).yellow());
+    }
+
+    Ok(Session { targets, scanner, output })
+}
+
+fn run_scan(args: &ScanArgs, history_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let Session { targets, scanner, output } = setup(args, false)?;
+
+    let mut history = history_path.and_then(|path| {
+        open_history(&path)
+            .map_err(|err| eprintln!("⚠️ {} Scan history disabled: {}", "Warning:".yellow(), err))
+            .ok()
+    });
+    if let Some(history) = &history {
+        status!("{}", format!("Scan History: {}", history.path().display()).yellow());
+    }
+
+    let options: Vec<String> = std::env::args().skip(1).collect();
+    let started_at = results::unix_now();
     let start_time = Instant::now();
 
-    // Scan subdomains with validated thread count
-    let found_domains = scan_subdomains(&args.domain, &wordlist, thread_count);
+    let mut reports = Vec::with_capacity(targets.len());
+    for (index, domain) in targets.iter().enumerate() {
+        if scanner.is_stopped() {
+            break;
+        }
+        if targets.len() > 1 {
+            status!("\n{}", format!("[{}/{}] Target: {}", index + 1, targets.len(), domain).cyan());
+        }
+        let target_started_at = results::format_timestamp(results::unix_now());
+        let report = scanner.scan(domain)?;
+        print_report(&report);
+        if let Some(history) = &mut history {
+            match history.record(&report, &target_started_at, &options) {
+                Ok(id) => status!("{}", format!("Stored as scan #{} in the history", id).blue()),
+                Err(err) => eprintln!("⚠️ {} Could not store the scan in the history: {}", "Warning:".yellow(), err),
+            }
+        }
+        reports.push(report);
+    }
+    let duration = start_time.elapsed();
 
-    // Print results
-    println!("\n==================================================");
-    println!("{}", "Scan Results".cyan());
-    println!("{}", format!("Scan completed in {:.2} seconds", start_time.elapsed().as_secs_f64()).green());
-    println!("{}", format!("Found {} subdomains:", found_domains.len()).green());
+    status!();
+    print_resolver_health(scanner.client().pool());
+    status!("{}", format!("  └─ Rate limiter: {}", scanner.client().limiter().status()).blue());
 
-    for discovered_domain in found_domains {
-        println!("{}", format!("  └─ {}", discovered_domain).magenta());
+    let found = reports.iter().map(|report| report.found).sum();
+    if targets.len() > 1 {
+        status!();
+        status!("{}", format!("Scanned {} targets in {:.2} seconds, found {} subdomains in total",
+            reports.len(), duration.as_secs_f64(), found
+        ).green());
+    }
+
+    let metadata = ScanMetadata {
+        domains: targets.clone(),
+        wordlist: format!("{:?}", args.wordlist).to_lowercase(),
+        wordlist_size: scanner.wordlist().len(),
+        concurrency: scanner.engine().concurrency(),
+        started_at: results::format_timestamp(started_at),
+        duration_secs: duration.as_secs_f64(),
+        found,
+        outcomes: scanner.outcomes().to_map(),
+        interrupted: scanner.is_stopped(),
+    };
+    output.finish(&metadata, &reports)?;
+    if let Some(path) = &args.output {
+        status!("{}", format!("Results written to {}", path).green());
+    }
+    if scanner.is_stopped() {
+        std::process::exit(130);
     }
 
     Ok(())
 }
 
+fn run_monitor(args: &MonitorArgs, history_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = history_path.ok_or_else(|| Error::History("Monitoring needs a history file: pass --history-file".to_string()))?;
+    let mut history = open_history(&path)?;
+
+    let mut notifier = Notifier::new(Duration::from_secs(args.webhook_timeout.max(1))).sink(Sink::Stdout);
+    if let Some(file) = &args.events_file {
+        notifier = notifier.sink(Sink::File(PathBuf::from(file)));
+    }
+    if let Some(url) = &args.webhook {
+        notifier = notifier.webhook(url)?;
+    }
+
+    let Session { targets, scanner, output } = setup(&args.scan, true)?;
+    status!("{}", "Monitoring:".yellow());
+    status!("{}", format!("  └─ History: {}", path.display()).blue());
+    status!("{}", format!("  └─ Interval: {}s", args.interval.as_secs()).blue());
+    for sink in notifier.sinks() {
+        let sink = match sink {
+            Sink::Stdout => "stdout".to_string(),
+            Sink::File(file) => file.display().to_string(),
+            Sink::Webhook(url) => url.to_string(),
+        };
+        status!("{}", format!("  └─ Events: {}", sink).blue());
+    }
+
+    let options: Vec<String> = std::env::args().skip(1).collect();
+    let started_at = results::unix_now();
+    let start_time = Instant::now();
+    let mut reports = Vec::new();
+
+    for round in 1.. {
+        // --output describes the latest round only, and every round gets the whole recursion budget
+        reports.clear();
+        scanner.reset_recursion_budget();
+        status!("\n{}", format!("[Round {}] {}", round, results::format_timestamp(results::unix_now())).cyan());
+        for domain in &targets {
+            if scanner.is_stopped() {
+                break;
+            }
+            let target_started_at = results::format_timestamp(results::unix_now());
+            // One failing target must not end the whole monitor
+            let report = match scanner.scan(domain) {
+                Ok(report) => report,
+                Err(err) => {
+                    eprintln!("⚠️ {} Could not scan {}: {}", "Warning:".yellow(), domain, err);
+                    continue;
+                },
+            };
+            print_report(&report);
+            // Neither stored nor compared: hosts the scan never reached would look disappeared
+            if report.interrupted {
+                reports.push(report);
+                break;
+            }
+
+            // Interrupted scans stored by `scan` runs are skipped for the same reason
+            let previous = history.latest_complete(domain).cloned();
+            let id = match history.record(&report, &target_started_at, &options) {
+                Ok(id) => id,
+                Err(err) => {
+                    eprintln!("⚠️ {} Could not store the scan in the history: {}", "Warning:".yellow(), err);
+                    reports.push(report);
+                    continue;
+                },
+            };
+            match (previous, history.get(id)) {
+                (Some(previous), Some(current)) => {
+                    let diff = ScanDiff::between(&previous, current);
+                    if diff.is_empty() {
+                        status!("{}", format!("No changes since scan #{}, stored as scan #{}", previous.id, id).blue());
+                    } else {
+                        status!("{}", format!("{} appeared, {} disappeared, {} changed since scan #{}, stored as scan #{}",
+                            diff.appeared.len(), diff.disappeared.len(), diff.changed.len(), previous.id, id
+                        ).yellow());
+                        for err in notifier.notify(&ChangeEvent::new(&diff)) {
+                            eprintln!("⚠️ {} {}", "Warning:".yellow(), err);
+                        }
+                    }
+                },
+                _ => status!("{}", format!("Baseline for {} stored as scan #{}", domain, id).blue()),
+            }
+            reports.push(report);
+        }
+
+        if args.once || scanner.is_stopped() {
+            break;
+        }
+        let next = results::unix_now() + args.interval.as_secs();
+        status!("{}", format!("Next round at {}", results::format_timestamp(next)).blue());
+        // Sleep in short steps so Ctrl-C does not wait for the whole interval
+        let deadline = Instant::now() + args.interval;
+        while Instant::now() < deadline && !scanner.is_stopped() {
+            std::thread::sleep(Duration::from_millis(200).min(deadline.saturating_duration_since(Instant::now())));
+        }
+        if scanner.is_stopped() {
+            break;
+        }
+    }
+
+    let metadata = ScanMetadata {
+        domains: targets.clone(),
+        wordlist: format!("{:?}", args.scan.wordlist).to_lowercase(),
+        wordlist_size: scanner.wordlist().len(),
+        concurrency: scanner.engine().concurrency(),
+        started_at: results::format_timestamp(started_at),
+        duration_secs: start_time.elapsed().as_secs_f64(),
+        found: reports.iter().map(|report| report.found).sum(),
+        outcomes: scanner.outcomes().to_map(),
+        interrupted: scanner.is_stopped(),
+    };
+    output.finish(&metadata, &reports)?;
+    if scanner.is_stopped() {
+        std::process::exit(130);
+    }
+    Ok(())
+}
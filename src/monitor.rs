@@ -0,0 +1,119 @@
+//! Change notifications for continuous monitoring.
+//!
+//! Each round rescans the monitored domains and diffs them against the scan
+//! history. Whenever a diff is not empty, a [`ChangeEvent`] carrying it is
+//! delivered to every sink of a [`Notifier`].
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::diff::ScanDiff;
+use crate::error::{Error, Result};
+use crate::http::{self, Url};
+use crate::results;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChangeEvent<'a> {
+    /// Always `subdomains_changed`, so sinks can tell these apart from other events later on
+    pub event: &'static str,
+    pub detected_at: String,
+    pub domain: &'a str,
+    pub appeared: usize,
+    pub disappeared: usize,
+    pub changed: usize,
+    pub diff: &'a ScanDiff,
+}
+
+impl<'a> ChangeEvent<'a> {
+    pub fn new(diff: &'a ScanDiff) -> Self {
+        ChangeEvent {
+            event: "subdomains_changed",
+            detected_at: results::format_timestamp(results::unix_now()),
+            domain: &diff.domain,
+            appeared: diff.appeared.len(),
+            disappeared: diff.disappeared.len(),
+            changed: diff.changed.len(),
+            diff,
+        }
+    }
+}
+
+/// Where change events are delivered
+#[derive(Clone, Debug)]
+pub enum Sink {
+    /// One JSON line per event on stdout
+    Stdout,
+    /// One JSON line per event appended to a file
+    File(PathBuf),
+    /// The event POSTed as a JSON body
+    Webhook(Url),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Notifier {
+    sinks: Vec<Sink>,
+    timeout: Duration,
+}
+
+impl Notifier {
+    /// A notifier without sinks; webhooks give up after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Notifier { sinks: Vec::new(), timeout }
+    }
+
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Add a webhook; only `http://` and `https://` URLs are accepted
+    pub fn webhook(self, url: &str) -> Result<Self> {
+        let url = Url::parse(url).ok_or_else(|| Error::InvalidOption(format!("Invalid webhook URL: {}", url)))?;
+        Ok(self.sink(Sink::Webhook(url)))
+    }
+
+    pub fn sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    /// Deliver `event` to every sink. A failing sink does not stop the others;
+    /// their errors are returned instead.
+    pub fn notify(&self, event: &ChangeEvent) -> Vec<Error> {
+        let json = match serde_json::to_string(event) {
+            Ok(json) => json,
+            Err(err) => return vec![Error::Notification(err.to_string())],
+        };
+        self.sinks
+            .iter()
+            .filter_map(|sink| self.deliver(sink, &json).err())
+            .collect()
+    }
+
+    fn deliver(&self, sink: &Sink, json: &str) -> Result<()> {
+        match sink {
+            Sink::Stdout => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{}", json)?;
+                stdout.flush()?;
+            },
+            Sink::File(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|err| {
+                    Error::Notification(format!("Could not open {}: {}", path.display(), err))
+                })?;
+                writeln!(file, "{}", json)?;
+            },
+            Sink::Webhook(url) => {
+                let response = http::post_json(url, json, self.timeout)
+                    .map_err(|err| Error::Notification(format!("Webhook {} failed: {}", url, err)))?;
+                if !(200..300).contains(&response.status) {
+                    return Err(Error::Notification(format!("Webhook {} answered {}", url, response.status)));
+                }
+            },
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-lookup outcomes and the counters used to report scan coverage.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dns::{Message, ResponseCode};
+
+/// How a single lookup ended
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// NOERROR with at least one answer record, or an alias whose target does not exist
+    Resolved,
+    /// The name does not exist
+    NxDomain,
+    /// The name exists but has no records of the requested type
+    NoData,
+    /// SERVFAIL, or any other server-side error code
+    ServFail,
+    /// The resolver declined to answer
+    Refused,
+    /// No response before the timeout
+    Timeout,
+    /// Socket-level failure (unreachable, connection refused, malformed reply)
+    NetworkError,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 7] = [
+        Outcome::Resolved,
+        Outcome::NxDomain,
+        Outcome::NoData,
+        Outcome::ServFail,
+        Outcome::Refused,
+        Outcome::Timeout,
+        Outcome::NetworkError,
+    ];
+
+    pub fn from_response(response: &Message) -> Self {
+        match response.rcode {
+            ResponseCode::NoError if response.answers.is_empty() => Outcome::NoData,
+            ResponseCode::NoError => Outcome::Resolved,
+            // NXDOMAIN after a CNAME is about the end of the chain (RFC 6604);
+            // the queried name is a dangling alias and does exist
+            ResponseCode::NxDomain if !response.answers.is_empty() => Outcome::Resolved,
+            ResponseCode::NxDomain => Outcome::NxDomain,
+            ResponseCode::Refused => Outcome::Refused,
+            _ => Outcome::ServFail,
+        }
+    }
+
+    pub fn from_error(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Outcome::Timeout,
+            _ => Outcome::NetworkError,
+        }
+    }
+
+    /// Outcomes that say nothing about the name and are worth retrying
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            Outcome::ServFail | Outcome::Refused | Outcome::Timeout | Outcome::NetworkError
+        )
+    }
+
+    fn index(self) -> usize {
+        Outcome::ALL.iter().position(|&o| o == self).unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Outcome::Resolved => "resolved",
+            Outcome::NxDomain => "nxdomain",
+            Outcome::NoData => "nodata",
+            Outcome::ServFail => "servfail",
+            Outcome::Refused => "refused",
+            Outcome::Timeout => "timeout",
+            Outcome::NetworkError => "network_error",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Final outcome of a lookup, with the response when one arrived
+#[derive(Clone, Debug)]
+pub struct Lookup {
+    pub outcome: Outcome,
+    pub response: Option<Message>,
+    /// Resolver used for the final attempt
+    pub resolver: Option<SocketAddr>,
+}
+
+/// Thread-safe tally of outcomes
+#[derive(Debug, Default)]
+pub struct OutcomeCounts {
+    counts: [AtomicU64; 7],
+}
+
+impl OutcomeCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, outcome: Outcome) {
+        self.counts[outcome.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add another tally into this one
+    pub fn absorb(&self, other: &OutcomeCounts) {
+        for outcome in Outcome::ALL {
+            self.counts[outcome.index()].fetch_add(other.get(outcome), Ordering::Relaxed);
+        }
+    }
+
+    /// Counts keyed by outcome label, for reports
+    pub fn to_map(&self) -> BTreeMap<String, u64> {
+        Outcome::ALL.iter().map(|&o| (o.to_string(), self.get(o))).collect()
+    }
+
+    pub fn get(&self, outcome: Outcome) -> u64 {
+        self.counts[outcome.index()].load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> u64 {
+        Outcome::ALL.iter().map(|&o| self.get(o)).sum()
+    }
+
+    /// Lookups that ended with a definitive answer about the name
+    pub fn conclusive(&self) -> u64 {
+        Outcome::ALL
+            .iter()
+            .filter(|o| !o.is_transient())
+            .map(|&o| self.get(o))
+            .sum()
+    }
+}
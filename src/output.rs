@@ -0,0 +1,211 @@
+//! Result export: plain text, JSON, streaming NDJSON and CSV.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::probe::HttpProbe;
+use crate::results::HostRecord;
+use crate::wildcard::Fingerprint;
+use crate::zonewalk::ZoneWalk;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON document with scan metadata and all results
+    Json,
+    /// One JSON record per line, written as each host is found
+    Ndjson,
+    /// host,ips,cnames,ttl,source rows, written as each host is found
+    Csv,
+}
+
+const CSV_HEADER: &str = "host,ips,cnames,ttl,source";
+
+/// Run-level details written alongside the results
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanMetadata {
+    pub domains: Vec<String>,
+    pub wordlist: String,
+    pub wordlist_size: usize,
+    pub concurrency: usize,
+    pub started_at: String,
+    pub duration_secs: f64,
+    pub found: usize,
+    pub outcomes: BTreeMap<String, u64>,
+    /// The run was interrupted; later targets were not scanned and results are partial
+    pub interrupted: bool,
+}
+
+/// Results and per-target details for one scanned domain
+#[derive(Clone, Debug, Serialize)]
+pub struct TargetReport {
+    pub domain: String,
+    pub duration_secs: f64,
+    pub found: usize,
+    pub outcomes: BTreeMap<String, u64>,
+    pub wildcards: Vec<String>,
+    pub wildcard_filtered: usize,
+    /// What each wildcard level answered with, for the terminal summary
+    #[serde(skip)]
+    pub wildcard_fingerprints: Vec<(String, Fingerprint)>,
+    /// Nameservers that allowed a full zone transfer
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub zone_transfers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_walk: Option<ZoneWalk>,
+    /// Netblocks swept for PTR records
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub netblocks: Vec<String>,
+    /// Non-address records of the apex itself (MX, TXT, CAA, DMARC, ...)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub apex_records: BTreeMap<String, Vec<String>>,
+    /// The scan was stopped early and the results are partial
+    pub interrupted: bool,
+    pub results: Vec<HostRecord>,
+}
+
+#[derive(Serialize)]
+struct JsonDocument<'a> {
+    metadata: &'a ScanMetadata,
+    targets: &'a [TargetReport],
+}
+
+/// One-line summary of an HTTP probe: url, status, redirects, title, server and size
+pub fn probe_line(probe: &HttpProbe) -> String {
+    let mut line = format!("{} {}", probe.url, probe.status);
+    if let (Some(last), Some(status)) = (probe.redirects.last(), probe.final_status) {
+        line.push_str(&format!(" -> {} {}", last, status));
+    }
+    if let Some(title) = &probe.title {
+        line.push_str(&format!(" \"{}\"", title));
+    }
+    if let Some(server) = &probe.server {
+        line.push_str(&format!(" [{}]", server));
+    }
+    line.push_str(&format!(" {}B", probe.content_length));
+    line
+}
+
+/// Plain one-line description of a record
+pub fn text_line(record: &HostRecord) -> String {
+    let mut line = record.hostname.clone();
+    let addresses = record.addresses();
+    if !addresses.is_empty() {
+        line.push_str(&format!(" [{}]", addresses.join(", ")));
+    }
+    if !record.cnames.is_empty() {
+        line.push_str(&format!(" (CNAME {})", record.cnames.join(" -> ")));
+    }
+    let types: Vec<&str> = record.records.keys().map(String::as_str).collect();
+    if !types.is_empty() {
+        line.push_str(&format!(" {{{}}}", types.join(",")));
+    }
+    if let Some(ttl) = record.ttl {
+        line.push_str(&format!(" ttl={}", ttl));
+    }
+    if let Some(takeover) = &record.takeover {
+        line.push_str(&format!(" [takeover {}: {}]", takeover.severity, takeover.reason));
+    }
+    line
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One CSV row; multi-valued columns are separated with `;`
+pub fn csv_row(record: &HostRecord) -> String {
+    [
+        csv_field(&record.hostname),
+        csv_field(&record.addresses().join(";")),
+        csv_field(&record.cnames.join(";")),
+        record.ttl.map(|ttl| ttl.to_string()).unwrap_or_default(),
+        csv_field(&record.source.to_string()),
+    ]
+    .join(",")
+}
+
+/// Destination for machine-readable results, shared by the scanning threads
+pub struct OutputWriter {
+    format: OutputFormat,
+    to_stdout: bool,
+    /// --silent: print bare hostnames on stdout as they are found
+    hosts_only: bool,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl OutputWriter {
+    /// Write to `path`, or to stdout when no path is given
+    pub fn new(format: OutputFormat, path: Option<&str>, silent: bool) -> io::Result<Self> {
+        let (mut out, to_stdout): (Box<dyn Write + Send>, bool) = match path {
+            Some(path) => (Box::new(BufWriter::new(File::create(path)?)), false),
+            None => (Box::new(io::stdout()), true),
+        };
+        if format == OutputFormat::Csv {
+            writeln!(out, "{}", CSV_HEADER)?;
+        }
+
+        let owns_stdout = to_stdout && format != OutputFormat::Text;
+        Ok(OutputWriter {
+            format,
+            to_stdout,
+            hosts_only: silent && !owns_stdout,
+            out: Mutex::new(out),
+        })
+    }
+
+    /// Whether structured results are going to stdout, leaving no room for decoration
+    pub fn owns_stdout(&self) -> bool {
+        self.to_stdout && self.format != OutputFormat::Text
+    }
+
+    /// Called once per newly discovered host; streaming formats write it straight away
+    pub fn discovered(&self, record: &HostRecord) -> io::Result<()> {
+        if self.hosts_only {
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", record.hostname)?;
+            stdout.flush()?;
+        }
+
+        let mut out = self.out.lock().unwrap();
+        match self.format {
+            OutputFormat::Ndjson => {
+                serde_json::to_writer(&mut *out, record)?;
+                out.write_all(b"\n")?;
+            },
+            OutputFormat::Csv => writeln!(out, "{}", csv_row(record))?,
+            OutputFormat::Text | OutputFormat::Json => return Ok(()),
+        }
+        out.flush()
+    }
+
+    /// Write everything that is only known once the scan is over
+    pub fn finish(&self, metadata: &ScanMetadata, targets: &[TargetReport]) -> io::Result<()> {
+        let mut out = self.out.lock().unwrap();
+        match self.format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, &JsonDocument { metadata, targets })?;
+                out.write_all(b"\n")?;
+            },
+            // Text on stdout is already shown by the terminal report
+            OutputFormat::Text if !self.to_stdout => {
+                for record in targets.iter().flat_map(|target| &target.results) {
+                    writeln!(out, "{}", text_line(record))?;
+                }
+            },
+            OutputFormat::Text | OutputFormat::Ndjson | OutputFormat::Csv => {},
+        }
+        out.flush()
+    }
+}
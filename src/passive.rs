@@ -0,0 +1,217 @@
+//! Passive datasets imported from local files.
+//!
+//! Certificate Transparency dumps and exports from other recon tools often
+//! know names no wordlist does. Files are read once at startup; each target
+//! then takes the names under it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::results::Source;
+
+// crt.sh fields holding certificate names; `name_value` is newline separated
+const CT_FIELDS: &[&str] = &["name_value", "common_name"];
+
+// Fields other JSON exports use for a hostname, including our own NDJSON
+const HOST_FIELDS: &[&str] = &["hostname", "host", "domain", "name", "subdomain"];
+
+#[derive(Debug, Default)]
+pub struct PassiveDataset {
+    /// Name -> the kind of dataset it first appeared in
+    names: HashMap<String, Source>,
+    pub files: usize,
+}
+
+impl PassiveDataset {
+    /// Read every file in `paths`, descending into directories.
+    /// `.json` files are crt.sh-style JSON or NDJSON, `.csv` files are
+    /// scanned field by field and anything else is a host list.
+    pub fn load(paths: &[String]) -> io::Result<Self> {
+        let mut dataset = PassiveDataset::default();
+        for path in paths {
+            dataset.load_path(Path::new(path))?;
+        }
+        Ok(dataset)
+    }
+
+    fn load_path(&mut self, path: &Path) -> io::Result<()> {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            entries.sort();
+            for entry in entries {
+                self.load_path(&entry)?;
+            }
+            return Ok(());
+        }
+
+        let contents = fs::read(path).map_err(|err| {
+            io::Error::new(err.kind(), format!("Could not read passive dataset {}: {}", path.display(), err))
+        })?;
+        let contents = String::from_utf8_lossy(&contents);
+        let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") | Some("ndjson") | Some("jsonl") => self.absorb_json(&contents).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("Invalid JSON in {}: {}", path.display(), err))
+            })?,
+            Some("csv") => self.absorb_csv(&contents),
+            _ => self.absorb_list(&contents),
+        }
+        self.files += 1;
+        Ok(())
+    }
+
+    /// A whole JSON document, or one JSON value per line
+    fn absorb_json(&mut self, contents: &str) -> serde_json::Result<()> {
+        match serde_json::from_str::<Value>(contents) {
+            Ok(value) => self.absorb_value(&value),
+            Err(_) => {
+                for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                    self.absorb_value(&serde_json::from_str(line)?);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn absorb_value(&mut self, value: &Value) {
+        match value {
+            Value::Array(items) => items.iter().for_each(|item| self.absorb_value(item)),
+            Value::String(name) => self.insert(name, Source::Import),
+            Value::Object(fields) => {
+                let named = CT_FIELDS
+                    .iter()
+                    .map(|&field| (field, Source::Ct))
+                    .chain(HOST_FIELDS.iter().map(|&field| (field, Source::Import)));
+                for (field, source) in named {
+                    if let Some(Value::String(names)) = fields.get(field) {
+                        names.lines().for_each(|name| self.insert(name, source));
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Every field of every row that looks like a hostname; headers fall out on their own
+    fn absorb_csv(&mut self, contents: &str) {
+        for line in contents.lines() {
+            line.split(',').for_each(|field| self.insert(field.trim().trim_matches('"'), Source::Import));
+        }
+    }
+
+    /// One host per line, with `#` comments
+    fn absorb_list(&mut self, contents: &str) {
+        for line in contents.lines() {
+            if let Some(name) = line.split_whitespace().next().filter(|name| !name.starts_with('#')) {
+                self.insert(name, Source::Import);
+            }
+        }
+    }
+
+    fn insert(&mut self, raw: &str, source: Source) {
+        if let Some(name) = clean_name(raw) {
+            self.names.entry(name).or_insert(source);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Names below `domain` with their sources, sorted by name
+    pub fn names_under(&self, domain: &str) -> Vec<(String, Source)> {
+        let suffix = format!(".{}", domain);
+        let mut names: Vec<(String, Source)> = self
+            .names
+            .iter()
+            .filter(|(name, _)| name.ends_with(&suffix))
+            .map(|(name, &source)| (name.clone(), source))
+            .collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names
+    }
+}
+
+/// Reduce URLs, `host:port` pairs and wildcard entries to a bare lowercase hostname
+fn clean_name(raw: &str) -> Option<String> {
+    let raw = raw.trim().to_lowercase();
+    let raw = raw.split_once("://").map_or(raw.as_str(), |(_, rest)| rest);
+    let host = raw.split(['/', ':', '?', '#']).next()?;
+    let host = host.trim_start_matches("*.").trim_end_matches('.');
+    let valid = host.contains('.')
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    valid.then(|| host.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(dataset: &PassiveDataset) -> Vec<(String, Source)> {
+        dataset.names_under("example.com")
+    }
+
+    fn name(name: &str, source: Source) -> (String, Source) {
+        (name.to_string(), source)
+    }
+
+    #[test]
+    fn crtsh_dumps_split_name_values() {
+        let mut dataset = PassiveDataset::default();
+        let dump = r#"[{"common_name": "www.example.com", "name_value": "*.example.com\nAPI.example.com\nmail.other.net"}]"#;
+        dataset.absorb_json(dump).unwrap();
+        assert_eq!(names(&dataset), [name("api.example.com", Source::Ct), name("www.example.com", Source::Ct)]);
+        // The wildcard leaves the apex, which is not under itself
+        assert_eq!(dataset.len(), 4);
+    }
+
+    #[test]
+    fn ndjson_lines_are_read_one_by_one() {
+        let mut dataset = PassiveDataset::default();
+        dataset.absorb_json("{\"hostname\": \"a.example.com\"}\n\n{\"host\": \"https://b.example.com:8443/login\"}\n").unwrap();
+        assert_eq!(names(&dataset), [name("a.example.com", Source::Import), name("b.example.com", Source::Import)]);
+        assert!(dataset.absorb_json("{\"host\": \"c.example.com\"}\nnot json\n").is_err());
+    }
+
+    #[test]
+    fn csv_fields_and_host_lists_are_cleaned() {
+        let mut dataset = PassiveDataset::default();
+        dataset.absorb_csv("host,ip\n\"vpn.example.com\",192.0.2.1\n");
+        dataset.absorb_list("# exported names\ndev.example.com. first seen 2024\n*.cdn.example.com\n");
+        assert_eq!(
+            names(&dataset),
+            [name("cdn.example.com", Source::Import), name("dev.example.com", Source::Import), name("vpn.example.com", Source::Import)]
+        );
+    }
+
+    #[test]
+    fn first_source_seen_wins() {
+        let mut dataset = PassiveDataset::default();
+        dataset.absorb_json(r#"[{"name_value": "www.example.com"}]"#).unwrap();
+        dataset.absorb_list("www.example.com\n");
+        assert_eq!(names(&dataset), [name("www.example.com", Source::Ct)]);
+    }
+
+    #[test]
+    fn names_that_are_not_hostnames_are_dropped() {
+        for raw in ["localhost", "bad name.example.com", "a..example.com", ""] {
+            assert_eq!(clean_name(raw), None, "{}", raw);
+        }
+        assert_eq!(clean_name(" HTTP://Shop.Example.com/cart?id=1 ").as_deref(), Some("shop.example.com"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Alterations of hostnames that were already found.
+//!
+//! Names in a zone tend to follow a scheme, so variations of known hosts
+//! (`api` -> `api2`, `dev-api`, `api-staging`, `staging.api`) are far more
+//! likely to exist than another word from a static list.
+
+use std::collections::HashSet;
+
+/// Environment and role words mixed into discovered names
+pub const DEFAULT_WORDS: &[&str] = &[
+    "dev", "development", "stage", "staging", "stg", "prod", "production",
+    "test", "qa", "uat", "preprod", "sandbox", "demo", "beta", "internal",
+    "int", "old", "new", "backup", "admin", "api", "v1", "v2",
+];
+
+#[derive(Debug)]
+pub struct Permutator {
+    words: Vec<String>,
+    limit: usize,
+}
+
+impl Permutator {
+    /// Mix `words` into discovered names, producing at most `limit` candidates per target
+    pub fn new(words: Vec<String>, limit: usize) -> Self {
+        Permutator { words, limit }
+    }
+
+    /// Candidate hostnames derived from `found`, all under `domain`, excluding the found names.
+    ///
+    /// The first label of each host is altered; labels of the other found hosts
+    /// are used as words too, so `dev` and `api` give `dev-api`. Hosts take turns
+    /// adding a candidate, so the limit is shared evenly rather than spent on the
+    /// first hosts in the list.
+    pub fn generate(&self, domain: &str, found: &[String]) -> Vec<String> {
+        let known: HashSet<&str> = found.iter().map(String::as_str).collect();
+        let mut words: Vec<&str> = self.words.iter().map(String::as_str).collect();
+        for hostname in found {
+            if let Some((label, _)) = hostname.split_once('.') {
+                if !words.contains(&label) {
+                    words.push(label);
+                }
+            }
+        }
+
+        let suffix = format!(".{}", domain);
+        let words = &words;
+        // Names are built lazily, so only the ones the limit lets through are ever allocated
+        let mut hosts: Vec<Box<dyn Iterator<Item = String> + '_>> = found
+            .iter()
+            .filter(|hostname| hostname.ends_with(&suffix))
+            .filter_map(|hostname| {
+                let (label, parent) = hostname.split_once('.')?;
+                let altered = number_variants(label)
+                    .into_iter()
+                    .chain(word_variants(label, words))
+                    .map(move |label| format!("{}.{}", label, parent));
+                // A new level in front of the host: staging.api.example.com
+                let nested = words.iter().filter(move |&&word| word != label).map(move |word| format!("{}.{}", word, hostname));
+                Some(Box::new(altered.chain(nested)) as Box<dyn Iterator<Item = String>>)
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+        while !hosts.is_empty() && candidates.len() < self.limit {
+            // Each host adds its next new name, and drops out once it has none left
+            hosts.retain_mut(|names| {
+                if candidates.len() >= self.limit {
+                    return true;
+                }
+                let next = names.find(|name| is_valid_hostname(name) && !known.contains(name.as_str()) && !seen.contains(name));
+                let Some(name) = next else { return false };
+                seen.insert(name.clone());
+                candidates.push(name);
+                true
+            });
+        }
+        candidates
+    }
+}
+
+/// `api` -> `api1`..`api3`; `web01` -> `web00`..`web09` and `web1`..`web9`
+fn number_variants(label: &str) -> Vec<String> {
+    let digits_at = label.find(|c: char| c.is_ascii_digit());
+    let Some(start) = digits_at else {
+        return (1..=3).map(|n| format!("{}{}", label, n)).collect();
+    };
+
+    let end = label[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(label.len(), |offset| start + offset);
+    let (head, number, tail) = (&label[..start], &label[start..end], &label[end..]);
+    let mut variants = Vec::new();
+    for n in 0..10 {
+        // Keep zero padding when the original had it
+        variants.push(format!("{}{:0width$}{}", head, n, tail, width = number.len()));
+        variants.push(format!("{}{}{}", head, n, tail));
+    }
+    variants.retain(|variant| variant != label);
+    variants
+}
+
+/// Words joined to the label with and without a dash, and words swapped for dash-separated parts
+fn word_variants<'a>(label: &'a str, words: &'a [&'a str]) -> impl Iterator<Item = String> + 'a {
+    let joined = words.iter().filter(move |&&word| word != label).flat_map(move |word| {
+        [
+            format!("{}-{}", word, label),
+            format!("{}-{}", label, word),
+            format!("{}{}", word, label),
+            format!("{}{}", label, word),
+        ]
+    });
+
+    // api-staging -> api-dev, api-prod, ...
+    let parts: Vec<&str> = label.split('-').collect();
+    let swappable: Vec<usize> = match parts.len() {
+        1 => Vec::new(),
+        _ => (0..parts.len()).filter(|&index| words.contains(&parts[index])).collect(),
+    };
+    let swapped = swappable.into_iter().flat_map(move |index| {
+        let (parts, part) = (parts.clone(), parts[index]);
+        words.iter().filter(move |&&word| word != part).map(move |&word| {
+            let mut replaced = parts.clone();
+            replaced[index] = word;
+            replaced.join("-")
+        })
+    });
+    joined.chain(swapped)
+}
+
+pub fn is_valid_hostname(name: &str) -> bool {
+    name.len() <= 253 && name.split('.').all(|label| {
+        !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_is_shared_between_hosts() {
+        let permutator = Permutator::new(vec!["dev".to_string()], 4);
+        let found = ["api.example.com".to_string(), "web.example.com".to_string()];
+        let candidates = permutator.generate("example.com", &found);
+        assert_eq!(candidates, ["api1.example.com", "web1.example.com", "api2.example.com", "web2.example.com"]);
+    }
+
+    #[test]
+    fn numbers_are_varied_keeping_their_padding() {
+        let variants = number_variants("web01");
+        assert!(variants.contains(&"web02".to_string()));
+        assert!(variants.contains(&"web2".to_string()));
+        assert!(!variants.contains(&"web01".to_string()));
+        assert_eq!(number_variants("api"), ["api1", "api2", "api3"]);
+    }
+
+    #[test]
+    fn words_are_joined_and_swapped_for_parts() {
+        let variants: Vec<String> = word_variants("api-staging", &["staging", "dev"]).collect();
+        for expected in ["dev-api-staging", "api-staging-dev", "devapi-staging", "api-stagingdev", "api-dev"] {
+            assert!(variants.contains(&expected.to_string()), "{} not in {:?}", expected, variants);
+        }
+        assert!(!variants.contains(&"api-staging".to_string()));
+    }
+
+    #[test]
+    fn found_names_and_other_domains_are_left_out() {
+        let permutator = Permutator::new(vec!["dev".to_string()], 1000);
+        let found = ["api.example.com".to_string(), "dev-api.example.com".to_string(), "www.other.net".to_string()];
+        let candidates = permutator.generate("example.com", &found);
+
+        assert!(candidates.contains(&"api-dev.example.com".to_string()));
+        assert!(candidates.contains(&"dev.api.example.com".to_string()));
+        // Labels of other found hosts are mixed in as words
+        assert!(candidates.contains(&"api-dev-api.example.com".to_string()));
+        assert!(!candidates.contains(&"dev-api.example.com".to_string()));
+        assert!(candidates.iter().all(|name| name.ends_with(".example.com") && is_valid_hostname(name)));
+        assert_eq!(candidates.len(), candidates.iter().collect::<HashSet<_>>().len());
+    }
+
+    #[test]
+    fn invalid_hostnames_are_rejected() {
+        assert!(is_valid_hostname("dev-api.example.com"));
+        assert!(!is_valid_hostname("-api.example.com"));
+        assert!(!is_valid_hostname("api..example.com"));
+        assert!(!is_valid_hostname(&format!("{}.example.com", "a".repeat(64))));
+    }
+}
@@ -0,0 +1,189 @@
+//! HTTP and HTTPS probing of discovered hosts.
+//!
+//! Each host is requested on every probe port, following redirects, and the
+//! status, title, Server header and size of the final page are recorded.
+//! Probes run as their own pass once the DNS passes are done, on a separate
+//! pool of workers with its own timeout. Ports 443, 8443 and 9443 use TLS
+//! unless a port is given an explicit scheme. TLS probes also keep the names
+//! listed on the host's certificate.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dns::{DnsClient, RecordType};
+use crate::http::Url;
+use crate::results::HostRecord;
+
+const MAX_REDIRECTS: usize = 5;
+
+// Longest page title kept
+const MAX_TITLE_CHARS: usize = 120;
+
+/// Ports spoken to over TLS when no scheme is given; everything else gets plain HTTP
+const TLS_PORTS: &[u16] = &[443, 8443, 9443];
+
+/// A port to probe and whether it is spoken to over TLS
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProbePort {
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl ProbePort {
+    /// `port` over TLS if it is one of the usual HTTPS ports
+    pub fn new(port: u16) -> Self {
+        ProbePort { port, tls: TLS_PORTS.contains(&port) }
+    }
+}
+
+impl FromStr for ProbePort {
+    type Err = String;
+
+    /// Accepts a bare port (`8080`) or one with a scheme (`https:4443`, `http:8443`)
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (tls, port) = match value.split_once(':') {
+            Some((scheme, port)) if scheme.eq_ignore_ascii_case("https") => (Some(true), port),
+            Some((scheme, port)) if scheme.eq_ignore_ascii_case("http") => (Some(false), port),
+            Some(_) => return Err(format!("Unknown probe scheme: {}", value)),
+            None => (None, value),
+        };
+        let port: u16 = port.parse().map_err(|_| format!("Invalid probe port: {}", value))?;
+        Ok(match tls {
+            Some(tls) => ProbePort { port, tls },
+            None => ProbePort::new(port),
+        })
+    }
+}
+
+impl fmt::Display for ProbePort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", if self.tls { "https" } else { "http" }, self.port)
+    }
+}
+
+/// What one host served on one port
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpProbe {
+    pub url: String,
+    pub status: u16,
+    /// Locations followed from `url`, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<String>,
+    /// Status of the last response when redirects were followed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    pub content_length: usize,
+    /// DNS names on the certificate `url` presented, for TLS ports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub certificate_names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Prober {
+    ports: Vec<ProbePort>,
+    concurrency: usize,
+    timeout: Duration,
+}
+
+impl Prober {
+    /// Probe `ports` with at most `concurrency` hosts being probed at once
+    pub fn new(ports: Vec<ProbePort>, concurrency: usize, timeout: Duration) -> Self {
+        Prober { ports, concurrency: concurrency.max(1), timeout }
+    }
+
+    /// Number of hosts probed at once
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Request `/` from the host on every probe port; ports that do not answer are left out
+    pub fn probe(&self, client: &DnsClient, record: &HostRecord) -> Vec<HttpProbe> {
+        let Some(addr) = first_address(record) else { return Vec::new() };
+        self.ports
+            .iter()
+            .filter_map(|probe_port| {
+                let url = Url::new(probe_port.tls, &record.hostname, probe_port.port, "/");
+                self.fetch_following(client, url, addr)
+            })
+            .collect()
+    }
+
+    fn fetch_following(&self, client: &DnsClient, url: Url, addr: IpAddr) -> Option<HttpProbe> {
+        let (first, certificate_names) = url.fetch(addr, self.timeout).ok()?;
+        let mut probe = HttpProbe {
+            url: url.to_string(),
+            status: first.status,
+            redirects: Vec::new(),
+            final_status: None,
+            title: None,
+            server: None,
+            content_length: 0,
+            certificate_names,
+        };
+
+        let (mut current, mut addr, mut response) = (url, addr, first);
+        while (300..400).contains(&response.status) && probe.redirects.len() < MAX_REDIRECTS {
+            let Some(next) = response.header("Location").and_then(|location| current.join(location)) else { break };
+            probe.redirects.push(next.to_string());
+            if next.host != current.host {
+                match resolve(client, &next.host) {
+                    Some(next_addr) => addr = next_addr,
+                    None => break,
+                }
+            }
+            match next.fetch(addr, self.timeout) {
+                Ok((next_response, _)) => response = next_response,
+                Err(_) => break,
+            }
+            probe.final_status = Some(response.status);
+            current = next;
+        }
+
+        probe.title = page_title(&response.body);
+        probe.server = response.header("Server").map(str::to_string);
+        probe.content_length = response
+            .header("Content-Length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(response.body.len());
+        Some(probe)
+    }
+}
+
+fn first_address(record: &HostRecord) -> Option<IpAddr> {
+    record
+        .ipv4
+        .first()
+        .map(|&ip| IpAddr::V4(ip))
+        .or_else(|| record.ipv6.first().map(|&ip| IpAddr::V6(ip)))
+}
+
+fn resolve(client: &DnsClient, host: &str) -> Option<IpAddr> {
+    if let Ok(ip) = host.parse() {
+        return Some(ip);
+    }
+    [RecordType::A, RecordType::AAAA].iter().find_map(|&rtype| {
+        client.lookup(host, rtype).response.and_then(|response| response.addresses().first().copied())
+    })
+}
+
+/// Text of the first `<title>` element, whitespace collapsed
+fn page_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title: String = body[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
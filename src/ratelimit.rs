@@ -0,0 +1,189 @@
+//! Token-bucket rate limiting for outgoing queries.
+//!
+//! A global bucket caps total queries per second and optional per-resolver
+//! buckets cap what any one nameserver sees. On top of that the limiter
+//! watches SERVFAIL and timeout responses: when they climb it halves the
+//! global ceiling, and raises it again slowly once they settle.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Length of the window failure rates are measured over
+const WINDOW: Duration = Duration::from_secs(1);
+
+// Queries a window needs before its failure rate is trusted
+const WINDOW_MIN_QUERIES: u64 = 20;
+
+// Failure ratios that trigger backing off and recovering
+const BACKOFF_RATIO: f64 = 0.10;
+const RECOVER_RATIO: f64 = 0.02;
+
+// The adaptive ceiling never drops below this
+const MIN_QPS: f64 = 1.0;
+
+/// Classic token bucket; waiting callers reserve a token and sleep until it is due
+#[derive(Debug)]
+pub struct TokenBucket {
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// `rate` tokens per second with a burst of a tenth of a second's worth (at least one)
+    pub fn new(rate: f64) -> Self {
+        let burst = (rate / 10.0).max(1.0);
+        TokenBucket {
+            state: Mutex::new(BucketState { rate, burst, tokens: burst, last_refill: Instant::now() }),
+        }
+    }
+
+    pub fn set_rate(&self, rate: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.refill();
+        state.rate = rate;
+        state.burst = (rate / 10.0).max(1.0);
+        state.tokens = state.tokens.min(state.burst);
+    }
+
+    /// Take one token, sleeping until it is available
+    pub fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            if !state.rate.is_finite() {
+                return;
+            }
+            state.refill();
+            state.tokens -= 1.0;
+            if state.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-state.tokens / state.rate)
+        };
+        thread::sleep(wait);
+    }
+}
+
+impl BucketState {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+    }
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    queries: u64,
+    failures: u64,
+    /// Adaptive ceiling on the global rate, if backing off
+    ceiling: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    configured: Option<f64>,
+    global: TokenBucket,
+    per_resolver: Vec<TokenBucket>,
+    window: Mutex<Window>,
+    /// Queries per second measured over the last complete window, as f64 bits
+    measured_qps: AtomicU64,
+}
+
+impl RateLimiter {
+    /// `rate` caps total qps, `resolver_rate` caps each of `resolvers` nameservers
+    pub fn new(rate: Option<f64>, resolver_rate: Option<f64>, resolvers: usize) -> Self {
+        let per_resolver = match resolver_rate {
+            Some(rate) => (0..resolvers).map(|_| TokenBucket::new(rate)).collect(),
+            None => Vec::new(),
+        };
+        RateLimiter {
+            configured: rate,
+            global: TokenBucket::new(rate.unwrap_or(f64::INFINITY)),
+            per_resolver,
+            window: Mutex::new(Window { started: Instant::now(), queries: 0, failures: 0, ceiling: None }),
+            measured_qps: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    /// Wait until a query to the resolver at `index` is allowed
+    pub fn acquire(&self, index: usize) {
+        self.global.acquire();
+        if let Some(bucket) = self.per_resolver.get(index) {
+            bucket.acquire();
+        }
+    }
+
+    /// Feed back whether a query ended in SERVFAIL or a timeout
+    pub fn record(&self, failed: bool) {
+        let mut window = self.window.lock().unwrap();
+        window.queries += 1;
+        if failed {
+            window.failures += 1;
+        }
+
+        let elapsed = window.started.elapsed();
+        if elapsed < WINDOW || window.queries < WINDOW_MIN_QUERIES {
+            return;
+        }
+
+        let qps = window.queries as f64 / elapsed.as_secs_f64();
+        let ratio = window.failures as f64 / window.queries as f64;
+        self.measured_qps.store(qps.to_bits(), Ordering::Relaxed);
+
+        let limit = self.configured.unwrap_or(f64::INFINITY);
+        let ceiling = if ratio > BACKOFF_RATIO {
+            Some((window.ceiling.unwrap_or(limit).min(qps) / 2.0).max(MIN_QPS))
+        } else if ratio < RECOVER_RATIO {
+            // Recover by a quarter per healthy window; without a configured
+            // rate the ceiling is lifted once it is well above real throughput
+            window.ceiling
+                .map(|ceiling| ceiling * 1.25)
+                .filter(|&ceiling| ceiling < limit && (self.configured.is_some() || ceiling < qps * 4.0))
+        } else {
+            window.ceiling
+        };
+
+        if ceiling != window.ceiling {
+            self.global.set_rate(ceiling.unwrap_or(limit).min(limit));
+        }
+        *window = Window { started: Instant::now(), queries: 0, failures: 0, ceiling };
+    }
+
+    pub fn measured_qps(&self) -> f64 {
+        f64::from_bits(self.measured_qps.load(Ordering::Relaxed))
+    }
+
+    /// Current global limit in qps, `None` when unlimited
+    pub fn effective_limit(&self) -> Option<f64> {
+        let ceiling = self.window.lock().unwrap().ceiling;
+        match (ceiling, self.configured) {
+            (Some(ceiling), Some(limit)) => Some(ceiling.min(limit)),
+            (ceiling, limit) => ceiling.or(limit),
+        }
+    }
+
+    pub fn is_backing_off(&self) -> bool {
+        self.window.lock().unwrap().ceiling.is_some()
+    }
+
+    /// Short description for the progress bar
+    pub fn status(&self) -> String {
+        let qps = self.measured_qps();
+        match self.effective_limit() {
+            Some(limit) if self.is_backing_off() => format!("{:.0} qps (backing off, limit {:.0})", qps, limit),
+            Some(limit) => format!("{:.0} qps (limit {:.0})", qps, limit),
+            None => format!("{:.0} qps", qps),
+        }
+    }
+}
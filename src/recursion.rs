@@ -0,0 +1,63 @@
+//! Recursive enumeration of discovered hosts.
+//!
+//! Hosts found at one level become base domains for the next, up to a maximum
+//! depth below the target. A budget shared by every target caps how many bases
+//! are brute-forced in total, so a zone full of hosts cannot blow up the run.
+//! Monitoring resets the budget at the start of every round.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug)]
+pub struct RecursionPolicy {
+    max_depth: usize,
+    filters: Vec<String>,
+    limit: usize,
+    remaining: AtomicUsize,
+}
+
+impl RecursionPolicy {
+    /// Recurse to `max_depth` labels below the target, into at most `limit` bases.
+    /// With `filters`, only hosts whose first label contains one of them are recursed into.
+    pub fn new(max_depth: usize, filters: &[String], limit: usize) -> Self {
+        RecursionPolicy {
+            max_depth: max_depth.max(1),
+            filters: filters.iter().map(|filter| filter.to_lowercase()).collect(),
+            limit,
+            remaining: AtomicUsize::new(limit),
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Bases still allowed across the whole run
+    pub fn remaining(&self) -> usize {
+        self.remaining.load(Ordering::Relaxed)
+    }
+
+    /// Allow the full `limit` again
+    pub fn reset(&self) {
+        self.remaining.store(self.limit, Ordering::Relaxed);
+    }
+
+    /// Whether `hostname` passes the filters
+    pub fn wants(&self, hostname: &str) -> bool {
+        let label = hostname.split('.').next().unwrap_or_default();
+        self.filters.is_empty() || self.filters.iter().any(|filter| label.contains(filter.as_str()))
+    }
+
+    /// Pick the next bases from `hosts`, spending the budget.
+    /// Returns the chosen bases and how many had to be dropped because the budget ran out.
+    pub fn select(&self, hosts: &[String]) -> (Vec<String>, usize) {
+        let wanted: Vec<&String> = hosts.iter().filter(|host| self.wants(host)).collect();
+        let granted = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left - left.min(wanted.len()))
+            })
+            .map_or(0, |left| left.min(wanted.len()));
+        let bases = wanted.iter().take(granted).map(|host| host.to_string()).collect();
+        (bases, wanted.len() - granted)
+    }
+}
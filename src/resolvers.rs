@@ -0,0 +1,229 @@
+//! Pool of upstream nameservers with round-robin selection and health tracking.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Consecutive failures before a resolver is benched
+const BENCH_THRESHOLD: u64 = 5;
+
+// First bench period; doubles every time the same resolver is benched again
+const BENCH_BASE: Duration = Duration::from_secs(5);
+const BENCH_MAX: Duration = Duration::from_secs(120);
+
+/// Result of a single exchange with a resolver, as far as its health is concerned
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Health {
+    Success,
+    Timeout,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Resolver {
+    pub addr: SocketAddr,
+    queries: AtomicU64,
+    successes: AtomicU64,
+    timeouts: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+    consecutive_failures: AtomicU64,
+    times_benched: AtomicU64,
+    benched_until: Mutex<Option<Instant>>,
+}
+
+impl Resolver {
+    fn new(addr: SocketAddr) -> Self {
+        Resolver {
+            addr,
+            queries: AtomicU64::new(0),
+            successes: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latency_micros: AtomicU64::new(0),
+            consecutive_failures: AtomicU64::new(0),
+            times_benched: AtomicU64::new(0),
+            benched_until: Mutex::new(None),
+        }
+    }
+
+    fn is_benched(&self, now: Instant) -> bool {
+        matches!(*self.benched_until.lock().unwrap(), Some(until) if until > now)
+    }
+
+    fn bench(&self) {
+        let times = self.times_benched.fetch_add(1, Ordering::Relaxed);
+        let period = BENCH_BASE
+            .checked_mul(1 << times.min(8))
+            .unwrap_or(BENCH_MAX)
+            .min(BENCH_MAX);
+        *self.benched_until.lock().unwrap() = Some(Instant::now() + period);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+    }
+
+    /// Snapshot of the counters for reporting
+    pub fn stats(&self) -> ResolverStats {
+        let queries = self.queries.load(Ordering::Relaxed);
+        let successes = self.successes.load(Ordering::Relaxed);
+        let latency = self.latency_micros.load(Ordering::Relaxed);
+        ResolverStats {
+            addr: self.addr,
+            queries,
+            successes,
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            avg_latency: Duration::from_micros(latency.checked_div(successes).unwrap_or(0)),
+            times_benched: self.times_benched.load(Ordering::Relaxed),
+            benched: self.is_benched(Instant::now()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResolverStats {
+    pub addr: SocketAddr,
+    pub queries: u64,
+    pub successes: u64,
+    pub timeouts: u64,
+    pub errors: u64,
+    pub avg_latency: Duration,
+    pub times_benched: u64,
+    pub benched: bool,
+}
+
+impl ResolverStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.queries == 0 {
+            0.0
+        } else {
+            (self.timeouts + self.errors) as f64 / self.queries as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolverPool {
+    resolvers: Vec<Resolver>,
+    cursor: AtomicUsize,
+}
+
+impl ResolverPool {
+    pub fn new(addrs: Vec<SocketAddr>) -> io::Result<Self> {
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Resolver pool needs at least one nameserver",
+            ));
+        }
+        Ok(ResolverPool {
+            resolvers: addrs.into_iter().map(Resolver::new).collect(),
+            cursor: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    pub fn resolvers(&self) -> &[Resolver] {
+        &self.resolvers
+    }
+
+    /// Pick the next healthy resolver in round-robin order.
+    /// When every resolver is benched, the one closest to coming back is used.
+    pub fn next(&self) -> (usize, SocketAddr) {
+        let now = Instant::now();
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed);
+        let count = self.resolvers.len();
+
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            if !self.resolvers[index].is_benched(now) {
+                return (index, self.resolvers[index].addr);
+            }
+        }
+
+        let index = (0..count)
+            .min_by_key(|&i| self.resolvers[i].benched_until.lock().unwrap().unwrap_or(now))
+            .unwrap_or(0);
+        (index, self.resolvers[index].addr)
+    }
+
+    /// Record the outcome of a query sent to the resolver at `index`
+    pub fn record(&self, index: usize, health: Health, latency: Duration) {
+        let resolver = &self.resolvers[index];
+        resolver.queries.fetch_add(1, Ordering::Relaxed);
+        match health {
+            Health::Success => {
+                resolver.successes.fetch_add(1, Ordering::Relaxed);
+                resolver.latency_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+                resolver.consecutive_failures.store(0, Ordering::Relaxed);
+                return;
+            },
+            Health::Timeout => resolver.timeouts.fetch_add(1, Ordering::Relaxed),
+            Health::Error => resolver.errors.fetch_add(1, Ordering::Relaxed),
+        };
+
+        let failures = resolver.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        // Never bench the last resolver standing
+        if failures >= BENCH_THRESHOLD && self.healthy_count() > 1 {
+            resolver.bench();
+        }
+    }
+
+    fn healthy_count(&self) -> usize {
+        let now = Instant::now();
+        self.resolvers.iter().filter(|r| !r.is_benched(now)).count()
+    }
+
+    pub fn stats(&self) -> Vec<ResolverStats> {
+        self.resolvers.iter().map(Resolver::stats).collect()
+    }
+}
+
+/// Parse `ip` or `ip:port`, defaulting to port 53
+pub fn parse_resolver(value: &str) -> io::Result<SocketAddr> {
+    let value = value.trim();
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, 53))
+        .map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid resolver address: {}", value),
+        ))
+}
+
+/// Load resolvers from a file with one address per line; `#` starts a comment
+pub fn load_resolvers_from_file(file_path: &str) -> io::Result<Vec<SocketAddr>> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Resolver list not found at path: {}", file_path),
+        ));
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    let mut resolvers = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let entry = line.split('#').next().unwrap_or("").trim();
+        if !entry.is_empty() {
+            resolvers.push(parse_resolver(entry)?);
+        }
+    }
+    Ok(resolvers)
+}
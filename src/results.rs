@@ -0,0 +1,234 @@
+//! Structured records for discovered hosts.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::dns::{Message, RData, ResourceRecord};
+use crate::probe::HttpProbe;
+use crate::takeover::Takeover;
+
+/// How a host was discovered
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Wordlist brute force
+    Bruteforce,
+    /// Alteration of an already discovered name
+    Permutation,
+    /// Open zone transfer from an authoritative nameserver
+    Axfr,
+    /// Walked NSEC chain of a signed zone
+    Nsec,
+    /// NSEC3 hash cracked against the wordlist
+    Nsec3,
+    /// Subject alternative name on a probed host's TLS certificate
+    TlsSan,
+    /// PTR record found by sweeping a discovered netblock
+    Ptr,
+    /// Certificate Transparency dump given to --import
+    Ct,
+    /// Host list or other recon export given to --import
+    Import,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Source::Bruteforce => "bruteforce",
+            Source::Permutation => "permutation",
+            Source::Axfr => "axfr",
+            Source::Nsec => "nsec",
+            Source::Nsec3 => "nsec3",
+            Source::TlsSan => "tls-san",
+            Source::Ptr => "ptr",
+            Source::Ct => "ct",
+            Source::Import => "import",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Everything learned about one discovered hostname
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostRecord {
+    pub hostname: String,
+    pub ipv4: Vec<Ipv4Addr>,
+    pub ipv6: Vec<Ipv6Addr>,
+    /// CNAME targets in the order they were followed from `hostname`
+    pub cnames: Vec<String>,
+    /// Answers for every other record type that was queried, keyed by type
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub records: BTreeMap<String, Vec<String>>,
+    /// Lowest TTL across every answer record seen for this host
+    pub ttl: Option<u32>,
+    /// Resolver that answered the lookup that found the host
+    pub resolver: Option<SocketAddr>,
+    pub source: Source,
+    /// Seconds since the Unix epoch when the host was found
+    #[serde(serialize_with = "serialize_timestamp", deserialize_with = "deserialize_timestamp")]
+    pub timestamp: u64,
+    /// Set when the CNAME chain looks claimable by someone else
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub takeover: Option<Takeover>,
+    /// Responses from --probe, one per port that answered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub http: Vec<HttpProbe>,
+}
+
+impl HostRecord {
+    pub fn new(hostname: &str, resolver: Option<SocketAddr>, source: Source) -> Self {
+        HostRecord {
+            hostname: hostname.to_string(),
+            ipv4: Vec::new(),
+            ipv6: Vec::new(),
+            cnames: Vec::new(),
+            records: BTreeMap::new(),
+            ttl: None,
+            resolver,
+            source,
+            timestamp: unix_now(),
+            takeover: None,
+            http: Vec::new(),
+        }
+    }
+
+    /// Merge the answer section of a response into the record
+    pub fn absorb(&mut self, response: &Message) {
+        self.absorb_records(&response.answers);
+    }
+
+    /// Merge records owned by this host, such as the ones from a zone transfer
+    pub fn absorb_records(&mut self, records: &[ResourceRecord]) {
+        for record in records {
+            self.ttl = Some(self.ttl.map_or(record.ttl, |ttl| ttl.min(record.ttl)));
+            match &record.data {
+                RData::A(ip) if !self.ipv4.contains(ip) => self.ipv4.push(*ip),
+                RData::AAAA(ip) if !self.ipv6.contains(ip) => self.ipv6.push(*ip),
+                RData::CNAME(target) if !self.cnames.contains(target) => self.cnames.push(target.clone()),
+                RData::A(_) | RData::AAAA(_) | RData::CNAME(_) => {},
+                data => {
+                    let values = self.records.entry(record.rtype.to_string()).or_default();
+                    let value = data.to_string();
+                    if !values.contains(&value) {
+                        values.push(value);
+                    }
+                },
+            }
+        }
+        self.ipv4.sort();
+        self.ipv6.sort();
+    }
+
+    /// All addresses as strings, IPv4 first
+    pub fn addresses(&self) -> Vec<String> {
+        self.ipv4
+            .iter()
+            .map(|ip| ip.to_string())
+            .chain(self.ipv6.iter().map(|ip| ip.to_string()))
+            .collect()
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn serialize_timestamp<S: Serializer>(secs: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_timestamp(*secs))
+}
+
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_timestamp(&text).ok_or_else(|| de::Error::custom(format!("invalid timestamp: {}", text)))
+}
+
+/// Format Unix seconds as an RFC 3339 UTC timestamp
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3_600, (rem % 3_600) / 60, rem % 60
+    )
+}
+
+/// Parse an RFC 3339 UTC timestamp as written by [`format_timestamp`] back into Unix seconds
+pub fn parse_timestamp(text: &str) -> Option<u64> {
+    let (date, time) = text.strip_suffix('Z')?.split_once('T')?;
+    let date: Vec<i64> = date.split('-').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    let time: Vec<u64> = time.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    let (&[year, month, day], &[hour, minute, second]) = (date.as_slice(), time.as_slice()) else {
+        return None;
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days-from-civil conversion, the inverse of the one above
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146_097 + doe - 719_468).ok()?;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_round_trip() {
+        for secs in [0, 951_782_400, 1_704_067_199, 1_709_164_800, 4_102_444_800] {
+            assert_eq!(parse_timestamp(&format_timestamp(secs)), Some(secs), "{}", format_timestamp(secs));
+        }
+    }
+
+    #[test]
+    fn known_dates_are_parsed() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        // Leap day, and the last second before a new year
+        assert_eq!(parse_timestamp("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse_timestamp("2023-12-31T23:59:59Z"), Some(1_704_067_199));
+    }
+
+    #[test]
+    fn malformed_timestamps_are_rejected() {
+        for text in [
+            "",
+            "2024-01-01 00:00:00Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00+02:00",
+            "2024-13-01T00:00:00Z",
+            "2024-01-32T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:60:00Z",
+            "2024-01T00:00:00Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(parse_timestamp(text), None, "{}", text);
+        }
+    }
+}
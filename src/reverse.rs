@@ -0,0 +1,103 @@
+//! Reverse DNS sweeps of the netblocks discovered hosts live in.
+//!
+//! Hosts on the same infrastructure often carry PTR records under the target
+//! even when no wordlist would guess their names. Every IPv4 address found is
+//! widened to its block and each address in the block is looked up.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+
+use crate::dns::{DnsClient, RData, RecordType};
+use crate::engine::Engine;
+use crate::results::HostRecord;
+
+/// Prefix lengths a sweep accepts; a /16 is already 65,536 lookups
+pub const PREFIXES: std::ops::RangeInclusive<u8> = 16..=32;
+
+/// Most addresses swept for one target, however many blocks its hosts span
+pub const MAX_ADDRESSES: u64 = 1 << 16;
+
+/// An IPv4 network in CIDR form
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Netblock {
+    network: Ipv4Addr,
+    prefix: u8,
+}
+
+impl Netblock {
+    /// The `/prefix` block that `ip` belongs to
+    pub fn containing(ip: Ipv4Addr, prefix: u8) -> Self {
+        let prefix = prefix.min(32);
+        let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+        Netblock { network: Ipv4Addr::from(u32::from(ip) & mask), prefix }
+    }
+
+    /// Number of addresses in the block
+    pub fn size(&self) -> u64 {
+        1 << (32 - self.prefix)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = Ipv4Addr> {
+        let start = u64::from(u32::from(self.network));
+        (start..start + self.size()).map(|ip| Ipv4Addr::from(ip as u32))
+    }
+}
+
+impl fmt::Display for Netblock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Distinct `/prefix` blocks of every IPv4 address in `records`, sorted, and how many were
+/// left out to keep the sweep within [`MAX_ADDRESSES`]
+pub fn netblocks(records: &[HostRecord], prefix: u8) -> (Vec<Netblock>, usize) {
+    let blocks: BTreeSet<Netblock> = records
+        .iter()
+        .flat_map(|record| &record.ipv4)
+        .map(|&ip| Netblock::containing(ip, prefix))
+        .collect();
+    let total = blocks.len();
+    let mut addresses = 0;
+    let kept: Vec<Netblock> = blocks
+        .into_iter()
+        .take_while(|block| {
+            addresses += block.size();
+            addresses <= MAX_ADDRESSES
+        })
+        .collect();
+    let skipped = total - kept.len();
+    (kept, skipped)
+}
+
+/// `192.0.2.10` -> `10.2.0.192.in-addr.arpa`
+fn ptr_name(ip: Ipv4Addr) -> String {
+    let [a, b, c, d] = ip.octets();
+    format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+}
+
+/// Look up the PTR records of every address in `blocks`, calling `done` after each one.
+/// Returns (address, name) pairs sorted by address; addresses without a PTR are left out.
+pub fn sweep<F>(client: &DnsClient, engine: &Engine, blocks: &[Netblock], done: F) -> Vec<(Ipv4Addr, String)>
+where
+    F: Fn() + Sync,
+{
+    let addresses: Vec<Ipv4Addr> = blocks.iter().flat_map(Netblock::addresses).collect();
+    let pointers = Mutex::new(Vec::new());
+
+    engine.run(&addresses, |&ip| {
+        let lookup = client.lookup(&ptr_name(ip), RecordType::PTR);
+        let names = lookup.response.iter().flat_map(|response| &response.answers).filter_map(|record| match &record.data {
+            RData::PTR(name) => Some((ip, name.trim_end_matches('.').to_lowercase())),
+            _ => None,
+        });
+        pointers.lock().unwrap().extend(names);
+        done();
+    });
+
+    let mut pointers = pointers.into_inner().unwrap();
+    pointers.sort();
+    pointers
+}
@@ -0,0 +1,1084 @@
+//! The scan pipeline behind a builder-style API.
+//!
+//! A [`Scanner`] owns the resolver pool, rate limiter and worker engine for a
+//! run and enumerates one target at a time. Progress and discoveries are
+//! reported through an optional event callback, so callers decide what gets
+//! printed or stored while the scan is still going.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::axfr;
+use crate::checkpoint::{Checkpoint, Tracker};
+use crate::dns::{self, DnsClient, Message, RecordType};
+use crate::engine::{Engine, StopHandle};
+use crate::error::{Error, Result};
+use crate::outcome::{Lookup, Outcome, OutcomeCounts};
+use crate::output::TargetReport;
+use crate::passive::PassiveDataset;
+use crate::permutations::{self, Permutator};
+use crate::probe::Prober;
+use crate::ratelimit::RateLimiter;
+use crate::recursion::RecursionPolicy;
+use crate::resolvers::ResolverPool;
+use crate::results::{HostRecord, Source};
+use crate::reverse;
+use crate::takeover::{self, FingerprintDb};
+use crate::wildcard::WildcardDetector;
+use crate::wordlist::DEFAULT_WORDLIST;
+use crate::zonewalk::{self, Denial, ZoneWalk};
+
+/// How much attention a notice deserves
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// A step of the scan is starting
+    Info,
+    /// Detail about the step before it
+    Detail,
+    /// Something that changes how results should be read, such as a wildcard
+    Warning,
+    /// A security finding, such as an open zone transfer
+    Finding,
+}
+
+/// Something that happened during a scan
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// A host was found; every host is reported once, as soon as it is known.
+    /// Hosts that need HTTP checks are reported once those finish.
+    Discovered(&'a HostRecord),
+    /// Note on what the scan is doing
+    Notice(Level, &'a str),
+    /// A pass over `total` lookups started, running `workers` at a time
+    PassStarted { total: u64, workers: usize },
+    /// One lookup of the current pass finished; `rate` describes the rate limiter
+    Progress { rate: &'a str },
+    /// The current pass finished
+    PassFinished,
+}
+
+type Callback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Configures a [`Scanner`]; every option has the CLI's default
+pub struct ScannerBuilder {
+    resolvers: Vec<SocketAddr>,
+    concurrency: usize,
+    timeout: Duration,
+    retries: usize,
+    rate: Option<f64>,
+    resolver_rate: Option<f64>,
+    record_types: Vec<RecordType>,
+    wordlist: Vec<String>,
+    recursion: Option<RecursionPolicy>,
+    recursive_wordlist: Option<Vec<String>>,
+    permutator: Option<Permutator>,
+    axfr_port: Option<u16>,
+    zone_walk: bool,
+    fingerprints: Option<FingerprintDb>,
+    takeover_concurrency: usize,
+    takeover_timeout: Duration,
+    prober: Option<Prober>,
+    tls_san: bool,
+    reverse_prefix: Option<u8>,
+    passive: Option<PassiveDataset>,
+    resolve_imported: bool,
+    checkpoint: Option<PathBuf>,
+    resume: bool,
+    on_event: Option<Callback>,
+}
+
+impl Default for ScannerBuilder {
+    fn default() -> Self {
+        ScannerBuilder {
+            resolvers: Vec::new(),
+            concurrency: 200,
+            timeout: Duration::from_secs(2),
+            retries: 2,
+            rate: None,
+            resolver_rate: None,
+            record_types: vec![RecordType::A, RecordType::AAAA],
+            wordlist: DEFAULT_WORDLIST.iter().map(|word| word.to_string()).collect(),
+            recursion: None,
+            recursive_wordlist: None,
+            permutator: None,
+            axfr_port: Some(53),
+            zone_walk: true,
+            fingerprints: Some(FingerprintDb::builtin()),
+            takeover_concurrency: 50,
+            takeover_timeout: Duration::from_secs(5),
+            prober: None,
+            tls_san: true,
+            reverse_prefix: None,
+            passive: None,
+            resolve_imported: false,
+            checkpoint: None,
+            resume: false,
+            on_event: None,
+        }
+    }
+}
+
+impl ScannerBuilder {
+    /// Nameservers to query; the system configuration is used when none are given
+    pub fn resolvers(mut self, resolvers: Vec<SocketAddr>) -> Self {
+        self.resolvers = resolvers;
+        self
+    }
+
+    /// Maximum number of lookups in flight at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Time to wait for a DNS response before retrying
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of times a failed DNS query is retried
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Maximum queries per second across all resolvers
+    pub fn rate(mut self, qps: Option<f64>) -> Self {
+        self.rate = qps;
+        self
+    }
+
+    /// Maximum queries per second sent to any single resolver
+    pub fn resolver_rate(mut self, qps: Option<f64>) -> Self {
+        self.resolver_rate = qps;
+        self
+    }
+
+    /// Record types queried for each candidate; duplicates are dropped
+    pub fn record_types(mut self, record_types: &[RecordType]) -> Self {
+        self.record_types.clear();
+        for &rtype in record_types {
+            if !self.record_types.contains(&rtype) {
+                self.record_types.push(rtype);
+            }
+        }
+        self
+    }
+
+    pub fn wordlist(mut self, wordlist: Vec<String>) -> Self {
+        self.wordlist = wordlist;
+        self
+    }
+
+    /// Brute-force discovered hosts again as new bases, as far as `policy` allows
+    pub fn recursion(mut self, policy: RecursionPolicy) -> Self {
+        self.recursion = Some(policy);
+        self
+    }
+
+    /// Smaller wordlist for the recursive levels
+    pub fn recursive_wordlist(mut self, wordlist: Vec<String>) -> Self {
+        self.recursive_wordlist = Some(wordlist);
+        self
+    }
+
+    /// Resolve alterations of the discovered names in a second pass
+    pub fn permutations(mut self, permutator: Permutator) -> Self {
+        self.permutator = Some(permutator);
+        self
+    }
+
+    /// Port for zone transfer attempts; `None` skips them
+    pub fn zone_transfer(mut self, port: Option<u16>) -> Self {
+        self.axfr_port = port;
+        self
+    }
+
+    /// Whether DNSSEC-signed targets are zone walked
+    pub fn zone_walk(mut self, enabled: bool) -> Self {
+        self.zone_walk = enabled;
+        self
+    }
+
+    /// Fingerprints for takeover checks; `None` skips them
+    pub fn takeover(mut self, fingerprints: Option<FingerprintDb>) -> Self {
+        self.fingerprints = fingerprints;
+        self
+    }
+
+    /// Workers and per-request timeout for fetching pages matched against takeover fingerprints
+    pub fn takeover_http(mut self, concurrency: usize, timeout: Duration) -> Self {
+        self.takeover_concurrency = concurrency;
+        self.takeover_timeout = timeout;
+        self
+    }
+
+    /// Probe discovered hosts over HTTP and HTTPS
+    pub fn probe(mut self, prober: Prober) -> Self {
+        self.prober = Some(prober);
+        self
+    }
+
+    /// Whether names on probed hosts' TLS certificates are resolved as new hosts
+    pub fn tls_san(mut self, enabled: bool) -> Self {
+        self.tls_san = enabled;
+        self
+    }
+
+    /// Sweep PTR records across the `/prefix` blocks of discovered addresses
+    pub fn reverse(mut self, prefix: u8) -> Self {
+        self.reverse_prefix = Some(prefix);
+        self
+    }
+
+    /// Merge names from passive datasets; with `resolve`, only live ones are kept
+    pub fn passive(mut self, dataset: PassiveDataset, resolve: bool) -> Self {
+        self.passive = Some(dataset);
+        self.resolve_imported = resolve;
+        self
+    }
+
+    /// Save the progress of the wordlist pass to `path`; with `resume`, continue from the checkpoint already there
+    pub fn checkpoint(mut self, path: PathBuf, resume: bool) -> Self {
+        self.checkpoint = Some(path);
+        self.resume = resume;
+        self
+    }
+
+    /// Called from the scanning threads for every [`Event`]
+    pub fn on_event<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn build(self) -> Result<Scanner> {
+        for (label, value) in [("rate", self.rate), ("resolver rate", self.resolver_rate)] {
+            if value.is_some_and(|qps| !qps.is_finite() || qps <= 0.0) {
+                return Err(Error::InvalidOption(format!("The {} must be a positive number of queries per second", label)));
+            }
+        }
+        if self.record_types.is_empty() {
+            return Err(Error::InvalidOption("At least one record type must be queried".to_string()));
+        }
+        if let Some(prefix) = self.reverse_prefix.filter(|prefix| !reverse::PREFIXES.contains(prefix)) {
+            return Err(Error::InvalidOption(format!(
+                "The reverse sweep prefix must be between /{} and /{}, not /{}",
+                reverse::PREFIXES.start(),
+                reverse::PREFIXES.end(),
+                prefix
+            )));
+        }
+
+        let resolvers = if self.resolvers.is_empty() { dns::system_nameservers() } else { self.resolvers };
+        if resolvers.is_empty() {
+            return Err(Error::NoResolvers);
+        }
+        let pool = ResolverPool::new(resolvers)?;
+        let limiter = RateLimiter::new(self.rate, self.resolver_rate, pool.len());
+        let client = DnsClient::new(Arc::new(pool), Arc::new(limiter), self.timeout.max(Duration::from_secs(1)), self.retries);
+
+        let mut engine = Engine::new(self.concurrency);
+        if let Some(callback) = self.on_event.clone() {
+            engine = engine.on_warning(move |message| callback(&Event::Notice(Level::Warning, message)));
+        }
+
+        Ok(Scanner {
+            client,
+            engine,
+            record_types: self.record_types,
+            wordlist: self.wordlist,
+            recursion: self.recursion,
+            recursive_wordlist: self.recursive_wordlist,
+            permutator: self.permutator,
+            axfr_port: self.axfr_port,
+            zone_walk: self.zone_walk,
+            fingerprints: self.fingerprints,
+            takeover_concurrency: self.takeover_concurrency,
+            takeover_timeout: self.takeover_timeout,
+            tls_san: self.tls_san && self.prober.is_some(),
+            prober: self.prober,
+            reverse_prefix: self.reverse_prefix,
+            passive: self.passive,
+            resolve_imported: self.resolve_imported,
+            checkpoint: self.checkpoint,
+            resume: self.resume,
+            on_event: self.on_event,
+            totals: OutcomeCounts::new(),
+        })
+    }
+}
+
+/// Per-run state shared by every target
+pub struct Scanner {
+    client: DnsClient,
+    engine: Engine,
+    record_types: Vec<RecordType>,
+    wordlist: Vec<String>,
+    recursion: Option<RecursionPolicy>,
+    recursive_wordlist: Option<Vec<String>>,
+    permutator: Option<Permutator>,
+    /// Zone transfer port, `None` when transfers are disabled
+    axfr_port: Option<u16>,
+    zone_walk: bool,
+    /// Takeover fingerprints, `None` when the check is disabled
+    fingerprints: Option<FingerprintDb>,
+    /// Workers fetching pages for takeover fingerprints
+    takeover_concurrency: usize,
+    takeover_timeout: Duration,
+    prober: Option<Prober>,
+    /// Resolve new names from the certificates the prober saw
+    tls_san: bool,
+    /// Prefix of the netblocks swept for PTR records, `None` when sweeping is disabled
+    reverse_prefix: Option<u8>,
+    /// Imported names, `None` when nothing was imported
+    passive: Option<PassiveDataset>,
+    /// Imported names must resolve; otherwise the ones nothing else found are added unresolved
+    resolve_imported: bool,
+    /// Checkpoint file of the wordlist pass, `None` when progress is not saved
+    checkpoint: Option<PathBuf>,
+    /// Continue from the checkpoint instead of starting a new one
+    resume: bool,
+    on_event: Option<Callback>,
+    /// Outcomes across every target scanned so far
+    totals: OutcomeCounts,
+}
+
+impl Scanner {
+    pub fn builder() -> ScannerBuilder {
+        ScannerBuilder::default()
+    }
+
+    pub fn client(&self) -> &DnsClient {
+        &self.client
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn record_types(&self) -> &[RecordType] {
+        &self.record_types
+    }
+
+    pub fn wordlist(&self) -> &[String] {
+        &self.wordlist
+    }
+
+    /// Handle that interrupts the scan from another thread. Lookups already in flight finish,
+    /// the remaining passes are skipped and [`Scanner::scan`] returns what was found so far.
+    pub fn stop_handle(&self) -> StopHandle {
+        self.engine.stop_handle()
+    }
+
+    /// True once the scan was interrupted through a [`StopHandle`]
+    pub fn is_stopped(&self) -> bool {
+        self.engine.is_stopped()
+    }
+
+    /// Give recursion its whole budget back, so a repeated run of the same targets recurses as deep
+    pub fn reset_recursion_budget(&self) {
+        if let Some(policy) = &self.recursion {
+            policy.reset();
+        }
+    }
+
+    /// Outcomes of every lookup made by the targets scanned so far
+    pub fn outcomes(&self) -> &OutcomeCounts {
+        &self.totals
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(callback) = &self.on_event {
+            callback(&event);
+        }
+    }
+
+    fn notice(&self, level: Level, message: &str) {
+        self.emit(Event::Notice(level, message));
+    }
+
+    /// Enumerate the subdomains of `domain`.
+    /// Fails only when a checkpoint cannot be resumed.
+    pub fn scan(&self, domain: &str) -> Result<TargetReport> {
+        let checkpoint = self.open_checkpoint(domain)?;
+        let outcomes = OutcomeCounts::new();
+        let wildcards = WildcardDetector::new(&self.record_types);
+
+        // Start timing
+        let start_time = Instant::now();
+
+        let (zone_transfers, transferred) = match self.axfr_port {
+            Some(port) => self.try_zone_transfer(domain, port),
+            None => (Vec::new(), Vec::new()),
+        };
+        let (zone_walk, walked) = if zone_transfers.is_empty() && self.zone_walk && !self.is_stopped() {
+            self.try_zone_walk(domain)
+        } else {
+            (None, Vec::new())
+        };
+
+        let imported = self.passive.as_ref().map(|dataset| dataset.names_under(domain)).unwrap_or_default();
+        let live_imports = if self.resolve_imported {
+            let known = transferred.iter().chain(&walked).map(|record| record.hostname.as_str()).collect();
+            self.resolve_imported(&wildcards, &outcomes, &imported, &known)
+        } else {
+            Vec::new()
+        };
+
+        // An open transfer or a complete walk already listed the whole zone
+        let mut found_domains: Vec<HostRecord> = if !zone_transfers.is_empty() {
+            self.notice(Level::Warning, "Zone transfer succeeded, skipping the wordlist scan");
+            transferred.into_iter().chain(live_imports).collect()
+        } else if zone_walk.as_ref().is_some_and(|walk| walk.complete) {
+            self.notice(Level::Warning, "Zone walk listed every name, skipping the wordlist scan");
+            walked.into_iter().chain(live_imports).collect()
+        } else {
+            // An incomplete NSEC3 chain may lack the hashes of names the wordlist would find,
+            // so the wordlist still runs, minus the names already cracked
+            let seed = walked.into_iter().chain(live_imports).collect();
+            self.enumerate(&wildcards, &outcomes, domain, seed, checkpoint)
+        };
+        if !self.resolve_imported {
+            found_domains.extend(self.unresolved_imports(&imported, &found_domains));
+        }
+        found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        let (netblocks, swept) = match self.reverse_prefix {
+            Some(prefix) if !self.is_stopped() => self.sweep_netblocks(prefix, domain, &found_domains),
+            _ => (Vec::new(), Vec::new()),
+        };
+        if !swept.is_empty() {
+            found_domains.extend(swept);
+            found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        }
+        self.check_over_http(&mut found_domains);
+        if self.tls_san && !self.is_stopped() {
+            found_domains.extend(self.harvest_certificate_names(domain, &found_domains));
+            found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        }
+        let duration = start_time.elapsed();
+
+        let interrupted = self.is_stopped();
+        let apex_records = if interrupted { BTreeMap::new() } else { self.collect_apex_records(domain) };
+
+        // The target is done, so there is nothing left to resume
+        if let (Some(path), false) = (&self.checkpoint, interrupted) {
+            let _ = fs::remove_file(path);
+        }
+
+        self.totals.absorb(&outcomes);
+        let detected = wildcards.detected();
+        Ok(TargetReport {
+            domain: domain.to_string(),
+            duration_secs: duration.as_secs_f64(),
+            found: found_domains.len(),
+            outcomes: outcomes.to_map(),
+            wildcards: detected.iter().map(|(level, _)| format!("*.{}", level)).collect(),
+            wildcard_filtered: wildcards.filtered(),
+            wildcard_fingerprints: detected,
+            zone_transfers,
+            zone_walk,
+            netblocks,
+            apex_records,
+            interrupted,
+            results: found_domains,
+        })
+    }
+
+    /// The checkpoint to continue from when resuming, otherwise a fresh one; `None` without a checkpoint file
+    fn open_checkpoint(&self, domain: &str) -> Result<Option<Checkpoint>> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(None);
+        };
+        if !self.resume {
+            return Ok(Some(Checkpoint::new(domain, &self.wordlist)));
+        }
+
+        let checkpoint = Checkpoint::load(path)?;
+        checkpoint.verify(domain, &self.wordlist)?;
+        self.notice(Level::Info, &format!(
+            "Resuming from checkpoint: {}/{} wordlist entries done, {} to retry, {} hosts found",
+            checkpoint.completed(),
+            checkpoint.wordlist_size,
+            checkpoint.retry.len(),
+            checkpoint.results.len()
+        ));
+        Ok(Some(checkpoint))
+    }
+
+    /// Resolve a candidate for each requested record type.
+    /// Returns the outcome that best describes the candidate and the lookups that got answers.
+    pub fn check_subdomain(&self, hostname: &str) -> (Outcome, Vec<Lookup>) {
+        let mut lookups = Vec::with_capacity(self.record_types.len());
+        for &rtype in &self.record_types {
+            let lookup = self.client.lookup(hostname, rtype);
+            // NXDOMAIN covers every type, so there is nothing left to ask
+            let nonexistent = lookup.outcome == Outcome::NxDomain;
+            lookups.push(lookup);
+            if nonexistent {
+                break;
+            }
+        }
+
+        // A resolved type wins, then anything that could not complete, then the first answer
+        let outcome = lookups
+            .iter()
+            .map(|lookup| lookup.outcome)
+            .find(|&outcome| outcome == Outcome::Resolved)
+            .or_else(|| lookups.iter().map(|lookup| lookup.outcome).find(|outcome| outcome.is_transient()))
+            .or_else(|| lookups.first().map(|lookup| lookup.outcome))
+            .unwrap_or(Outcome::NetworkError);
+
+        lookups.retain(|lookup| lookup.outcome == Outcome::Resolved && lookup.response.is_some());
+        (outcome, lookups)
+    }
+
+    /// Query the apex itself for the non-address record types, plus DMARC when TXT is wanted
+    fn collect_apex_records(&self, domain: &str) -> BTreeMap<String, Vec<String>> {
+        let mut records = BTreeMap::new();
+        let mut queries: Vec<(String, RecordType, String)> = self
+            .record_types
+            .iter()
+            .filter(|rtype| !matches!(rtype, RecordType::A | RecordType::AAAA | RecordType::CNAME))
+            .map(|&rtype| (domain.to_string(), rtype, rtype.to_string()))
+            .collect();
+        if self.record_types.contains(&RecordType::TXT) {
+            queries.push((format!("_dmarc.{}", domain), RecordType::TXT, "DMARC".to_string()));
+        }
+
+        for (name, rtype, label) in queries {
+            let lookup = self.client.lookup(&name, rtype);
+            let values: Vec<String> = lookup
+                .response
+                .iter()
+                .flat_map(|response| &response.answers)
+                .filter(|record| record.rtype == rtype)
+                .map(|record| record.data.to_string())
+                .collect();
+            if !values.is_empty() {
+                records.insert(label, values);
+            }
+        }
+        records
+    }
+
+    /// Brute-force every wordlist entry under each of `bases` in a single engine run, skipping `known` hosts
+    fn scan_subdomains(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        bases: &[String],
+        wordlist: &[String],
+        known: &HashSet<String>
+    ) -> Vec<HostRecord> {
+        let candidates: Vec<String> = bases
+            .iter()
+            .flat_map(|base| wordlist.iter().map(move |subdomain| format!("{}.{}", subdomain, base)))
+            .filter(|candidate| !known.contains(candidate))
+            .collect();
+        self.scan_candidates(wildcards, outcomes, &candidates, Source::Bruteforce)
+    }
+
+    /// Per-host checks that need only DNS, run before a host is reported
+    fn inspect_host(&self, record: &mut HostRecord) {
+        if let Some(db) = &self.fingerprints {
+            record.takeover = takeover::check_dangling(&self.client, db, record);
+        }
+    }
+
+    /// Whether `record` waits for [`Scanner::check_over_http`] before it is reported
+    fn needs_http(&self, record: &HostRecord) -> bool {
+        self.prober.is_some() || self.needs_page_check(record)
+    }
+
+    fn needs_page_check(&self, record: &HostRecord) -> bool {
+        record.takeover.is_none() && self.fingerprints.as_ref().is_some_and(|db| takeover::needs_page_check(db, record))
+    }
+
+    /// Report a host found by a DNS pass, unless [`Scanner::check_over_http`] reports it later
+    fn report(&self, record: &HostRecord) {
+        if !self.needs_http(record) {
+            self.emit(Event::Discovered(record));
+        }
+    }
+
+    /// Match takeover fingerprints, then probe, each on workers of its own, and report the hosts DNS passes held back.
+    /// Keeping this out of the DNS passes means slow web servers never stall the lookups.
+    /// Hosts an interrupt leaves unchecked are reported as they are.
+    fn check_over_http(&self, hosts: &mut [HostRecord]) {
+        let held: Vec<usize> = (0..hosts.len()).filter(|&index| self.needs_http(&hosts[index])).collect();
+        let mut reported = vec![false; hosts.len()];
+
+        if let Some(db) = &self.fingerprints {
+            let pending: Vec<usize> = held.iter().copied().filter(|&index| self.needs_page_check(&hosts[index])).collect();
+            let message = format!("Matching takeover fingerprints on {} hosts...", pending.len());
+            let checked = self.http_pass(hosts, &pending, &message, self.takeover_concurrency, self.prober.is_none(), |record| {
+                record.takeover = takeover::check_page(db, record, self.takeover_timeout);
+            });
+            if self.prober.is_none() {
+                checked.iter().for_each(|&index| reported[index] = true);
+            }
+        }
+        if let Some(prober) = &self.prober {
+            let message = format!("Probing {} hosts over HTTP...", held.len());
+            let checked = self.http_pass(hosts, &held, &message, prober.concurrency(), true, |record| {
+                record.http = prober.probe(&self.client, record);
+            });
+            checked.iter().for_each(|&index| reported[index] = true);
+        }
+
+        for index in held {
+            if !reported[index] {
+                self.emit(Event::Discovered(&hosts[index]));
+            }
+        }
+    }
+
+    /// Run `check` on the `indices` of `hosts` with `concurrency` workers; with `report`, each host is reported once checked.
+    /// Returns the indices that were checked before any interrupt.
+    fn http_pass<F>(&self, hosts: &mut [HostRecord], indices: &[usize], message: &str, concurrency: usize, report: bool, check: F) -> Vec<usize>
+    where
+        F: Fn(&mut HostRecord) + Sync,
+    {
+        if indices.is_empty() || self.is_stopped() {
+            return Vec::new();
+        }
+        self.notice(Level::Info, message);
+        let engine = self.engine.with_concurrency(concurrency);
+        self.emit(Event::PassStarted { total: indices.len() as u64, workers: engine.concurrency().min(indices.len()) });
+        let checked = Mutex::new(Vec::new());
+        let view: &[HostRecord] = hosts;
+        engine.run(indices, |&index| {
+            let mut record = view[index].clone();
+            check(&mut record);
+            if report {
+                self.emit(Event::Discovered(&record));
+            }
+            checked.lock().unwrap().push((index, record));
+            self.emit(Event::Progress { rate: &self.client.limiter().status() });
+        });
+        self.emit(Event::PassFinished);
+
+        let checked = checked.into_inner().unwrap();
+        let indices = checked.iter().map(|(index, _)| *index).collect();
+        for (index, record) in checked {
+            hosts[index] = record;
+        }
+        indices
+    }
+
+    /// Resolve full candidate hostnames, keeping the ones that are not wildcard echoes
+    fn scan_candidates(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        candidates: &[String],
+        source: Source
+    ) -> Vec<HostRecord> {
+        self.scan_candidates_tracked(wildcards, outcomes, candidates, source, None)
+    }
+
+    /// [`Scanner::scan_candidates`], telling `tracker` about every finished lookup
+    fn scan_candidates_tracked(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        candidates: &[String],
+        source: Source,
+        tracker: Option<&Tracker>
+    ) -> Vec<HostRecord> {
+        let found_domains = Mutex::new(HashMap::new());
+        self.emit(Event::PassStarted {
+            total: candidates.len() as u64,
+            workers: self.engine.concurrency().min(candidates.len()),
+        });
+
+        self.engine.run(candidates, |hostname| {
+            let (outcome, answered) = self.check_subdomain(hostname);
+            outcomes.record(outcome);
+            let mut discovered = None;
+            if let Some(first) = answered.first() {
+                let responses: Vec<&Message> = answered.iter().filter_map(|lookup| lookup.response.as_ref()).collect();
+                if !wildcards.is_wildcard_hit(&self.client, hostname, &responses) {
+                    let mut record = HostRecord::new(hostname, first.resolver, source);
+                    for response in responses {
+                        record.absorb(response);
+                    }
+                    self.inspect_host(&mut record);
+                    let mut domains = found_domains.lock().unwrap();
+                    if !domains.contains_key(&record.hostname) {
+                        self.report(&record);
+                        discovered = tracker.is_some().then(|| record.clone());
+                        domains.insert(record.hostname.clone(), record);
+                    }
+                }
+            }
+            if let Some(tracker) = tracker {
+                if let Err(err) = tracker.finished(hostname, outcome, discovered.as_ref()) {
+                    self.notice(Level::Warning, &format!("⚠️ Could not save checkpoint: {}", err));
+                }
+            }
+            self.emit(Event::Progress { rate: &self.client.limiter().status() });
+        });
+
+        self.emit(Event::PassFinished);
+
+        // Convert the Mutex<HashMap> to a Vec sorted by hostname
+        let mut results: Vec<HostRecord> = found_domains.into_inner().unwrap().into_values().collect();
+        results.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        results
+    }
+
+    /// Brute-force below the hosts `found` so far, one level at a time, up to the policy's depth
+    fn scan_recursive(
+        &self,
+        policy: &RecursionPolicy,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        found: &[HostRecord]
+    ) -> Vec<HostRecord> {
+        let wordlist = self.recursive_wordlist.as_deref().unwrap_or(&self.wordlist);
+        let mut discovered = Vec::new();
+        let mut frontier: Vec<String> = found.iter().map(|record| record.hostname.clone()).collect();
+        // Hosts from zone transfers, zone walks or imports may already sit below the bases
+        let mut known: HashSet<String> = frontier.iter().cloned().collect();
+
+        for depth in 2..=policy.max_depth() {
+            if self.is_stopped() {
+                break;
+            }
+            let (bases, skipped) = policy.select(&frontier);
+            if skipped > 0 {
+                self.notice(Level::Warning, &format!("⚠️ Recursion limit reached, not recursing into {} more hosts", skipped));
+            }
+            if bases.is_empty() {
+                break;
+            }
+
+            self.notice(Level::Info, &format!("Recursing into {} hosts at depth {}...", bases.len(), depth));
+            // Every new base is its own level and gets its own wildcard check
+            self.engine.run(&bases, |base| {
+                if wildcards.fingerprint(&self.client, base).is_some() {
+                    self.notice(Level::Warning, &format!("⚠️ Wildcard DNS detected for *.{}, matching hits will be filtered", base));
+                }
+            });
+
+            let level = self.scan_subdomains(wildcards, outcomes, &bases, wordlist, &known);
+            frontier = level.iter().map(|record| record.hostname.clone()).collect();
+            known.extend(frontier.iter().cloned());
+            discovered.extend(level);
+        }
+        discovered
+    }
+
+    /// Ask each nameserver of `domain` for the whole zone.
+    /// Returns the servers that allowed it and the hosts from the first successful transfer.
+    fn try_zone_transfer(&self, domain: &str, port: u16) -> (Vec<String>, Vec<HostRecord>) {
+        self.notice(Level::Info, "Attempting zone transfer...");
+        let mut allowed = Vec::new();
+        let mut hosts = Vec::new();
+        for transfer in axfr::attempt(&self.client, domain, port, &self.stop_handle()) {
+            let label = format!("{} ({})", transfer.nameserver, transfer.addr);
+            match transfer.result {
+                Ok(records) => {
+                    self.notice(Level::Finding, &format!("⚠️ Zone transfer allowed by {}: {} records", label, records.len()));
+                    if allowed.is_empty() {
+                        hosts = axfr::hosts(domain, &records, transfer.addr);
+                    }
+                    allowed.push(label);
+                },
+                Err(err) => self.notice(Level::Detail, &format!("{}: {}", label, err)),
+            }
+        }
+
+        for record in &mut hosts {
+            self.inspect_host(record);
+            self.report(record);
+        }
+        (allowed, hosts)
+    }
+
+    /// Walk the NSEC chain, or collect and crack NSEC3 hashes, when the target is DNSSEC-signed
+    fn try_zone_walk(&self, domain: &str) -> (Option<ZoneWalk>, Vec<HostRecord>) {
+        self.notice(Level::Info, "Checking for DNSSEC zone walking...");
+        let Some(denial) = zonewalk::detect(&self.client, domain) else {
+            self.notice(Level::Detail, "No NSEC or NSEC3 records, zone walking skipped");
+            return (None, Vec::new());
+        };
+
+        let (walk, names, source) = match denial {
+            Denial::Nsec => {
+                let (names, complete) = zonewalk::walk_nsec(&self.client, domain, &self.stop_handle());
+                let state = match (complete, self.is_stopped()) {
+                    (true, _) => "",
+                    (false, true) => " (interrupted, incomplete)",
+                    (false, false) => " (chain broken, incomplete)",
+                };
+                self.notice(Level::Finding, &format!("⚠️ Walked the NSEC chain of {}: {} names{}", domain, names.len(), state));
+                let walk = ZoneWalk { method: "nsec".to_string(), complete, names: names.len(), hashes: None, uncracked: None };
+                (walk, names, Source::Nsec)
+            },
+            Denial::Nsec3 { salt, iterations } => {
+                let chain = zonewalk::collect_nsec3(&self.client, &self.engine, domain);
+                let names = zonewalk::crack(&chain, domain, &salt, iterations, &self.wordlist, &self.engine);
+                let hashes = chain.hashes();
+                let apex = zonewalk::nsec3_hash(domain, &salt, iterations)
+                    .is_some_and(|hash| hashes.contains(hash.as_str()));
+                let uncracked = hashes.len().saturating_sub(names.len() + usize::from(apex));
+                self.notice(Level::Finding, &format!("⚠️ Collected {} NSEC3 hashes of {} in {} queries, cracked {} ({} remain)",
+                    hashes.len(), domain, chain.queries, names.len(), uncracked
+                ));
+                let walk = ZoneWalk {
+                    method: "nsec3".to_string(),
+                    complete: chain.is_complete() && uncracked == 0,
+                    names: names.len(),
+                    hashes: Some(hashes.len()),
+                    uncracked: Some(uncracked),
+                };
+                (walk, names, Source::Nsec3)
+            },
+        };
+
+        // Wildcard owners stand for names that do not exist
+        let names: Vec<String> = names.into_iter().filter(|name| !name.starts_with("*.")).collect();
+        (Some(walk), self.resolve_walked(&names, source))
+    }
+
+    /// Look up the records of names known from zone data or certificates; they are kept even when none of the queried types answer
+    fn resolve_walked(&self, names: &[String], source: Source) -> Vec<HostRecord> {
+        let hosts = Mutex::new(Vec::with_capacity(names.len()));
+        self.engine.run(names, |name| {
+            let (_, answered) = self.check_subdomain(name);
+            let mut record = HostRecord::new(name, answered.first().and_then(|lookup| lookup.resolver), source);
+            for response in answered.iter().filter_map(|lookup| lookup.response.as_ref()) {
+                record.absorb(response);
+            }
+            self.inspect_host(&mut record);
+            self.report(&record);
+            hosts.lock().unwrap().push(record);
+        });
+
+        let mut hosts = hosts.into_inner().unwrap();
+        hosts.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        hosts
+    }
+
+    /// Look up imported names that are not already `known`, keeping live hosts that are not wildcard echoes
+    fn resolve_imported(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        imported: &[(String, Source)],
+        known: &HashSet<&str>
+    ) -> Vec<HostRecord> {
+        let names: Vec<&(String, Source)> = imported.iter().filter(|(name, _)| !known.contains(name.as_str())).collect();
+        if names.is_empty() {
+            return Vec::new();
+        }
+
+        self.notice(Level::Info, &format!("Resolving {} names from passive datasets...", names.len()));
+        let mut hosts = Vec::new();
+        for source in [Source::Ct, Source::Import] {
+            let candidates: Vec<String> = names.iter().filter(|(_, from)| *from == source).map(|(name, _)| name.clone()).collect();
+            if !candidates.is_empty() {
+                hosts.extend(self.scan_candidates(wildcards, outcomes, &candidates, source));
+            }
+        }
+        self.notice(Level::Detail, &format!("{} of {} imported names are live", hosts.len(), names.len()));
+        hosts
+    }
+
+    /// Records for imported names that no active technique found, taken as they are
+    fn unresolved_imports(&self, imported: &[(String, Source)], found: &[HostRecord]) -> Vec<HostRecord> {
+        let found: HashSet<&str> = found.iter().map(|record| record.hostname.as_str()).collect();
+        let hosts: Vec<HostRecord> = imported
+            .iter()
+            .filter(|(name, _)| !found.contains(name.as_str()))
+            .map(|(name, source)| HostRecord::new(name, None, *source))
+            .collect();
+        if !hosts.is_empty() {
+            self.notice(Level::Info, &format!("Adding {} names only seen in passive datasets", hosts.len()));
+        }
+        for record in &hosts {
+            self.report(record);
+        }
+        hosts
+    }
+
+    /// Look up PTR records across the `/prefix` blocks of the hosts `found`, resolving new names under `domain`.
+    /// Returns the swept blocks and the new hosts.
+    fn sweep_netblocks(&self, prefix: u8, domain: &str, found: &[HostRecord]) -> (Vec<String>, Vec<HostRecord>) {
+        let (blocks, skipped) = reverse::netblocks(found, prefix);
+        if skipped > 0 {
+            self.notice(Level::Warning, &format!("⚠️ Skipping {} netblocks to sweep at most {} addresses", skipped, reverse::MAX_ADDRESSES));
+        }
+        if blocks.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let addresses: u64 = blocks.iter().map(|block| block.size()).sum();
+        self.notice(Level::Info, &format!("Sweeping PTR records of {} addresses in {} netblocks...", addresses, blocks.len()));
+        self.emit(Event::PassStarted { total: addresses, workers: self.engine.concurrency().min(addresses as usize) });
+        let pointers = reverse::sweep(&self.client, &self.engine, &blocks, || {
+            self.emit(Event::Progress { rate: &self.client.limiter().status() });
+        });
+        self.emit(Event::PassFinished);
+
+        let suffix = format!(".{}", domain);
+        let known: HashSet<&str> = found.iter().map(|record| record.hostname.as_str()).collect();
+        let mut names: Vec<String> = pointers
+            .iter()
+            .map(|(_, name)| name)
+            .filter(|name| name.ends_with(&suffix) && !known.contains(name.as_str()) && permutations::is_valid_hostname(name))
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        self.notice(Level::Detail, &format!("{} PTR records, {} new names under {}", pointers.len(), names.len(), domain));
+
+        let blocks = blocks.iter().map(|block| block.to_string()).collect();
+        (blocks, self.resolve_walked(&names, Source::Ptr))
+    }
+
+    /// Resolve names under `domain` from the certificates of probed hosts, until the new hosts' certificates add nothing
+    fn harvest_certificate_names(&self, domain: &str, found: &[HostRecord]) -> Vec<HostRecord> {
+        let suffix = format!(".{}", domain);
+        let mut known: HashSet<String> = found.iter().map(|record| record.hostname.clone()).collect();
+        let mut harvested = Vec::new();
+        let mut names = new_certificate_names(found, &suffix, &mut known);
+        while !names.is_empty() {
+            self.notice(Level::Info, &format!("Resolving {} new names from TLS certificates...", names.len()));
+            let mut hosts = self.resolve_walked(&names, Source::TlsSan);
+            self.check_over_http(&mut hosts);
+            names = new_certificate_names(&hosts, &suffix, &mut known);
+            harvested.extend(hosts);
+        }
+        harvested
+    }
+
+    /// Wildcard detection, the wordlist scan and any recursion or permutation passes.
+    /// `seed` holds hosts already known from zone walking or imports and is not looked up again.
+    /// The wordlist pass saves its progress to `checkpoint` when one is given.
+    fn enumerate(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        domain: &str,
+        seed: Vec<HostRecord>,
+        checkpoint: Option<Checkpoint>
+    ) -> Vec<HostRecord> {
+        let mut found_domains = seed;
+        // Probe the apex for a wildcard before spending the wordlist on it
+        self.notice(Level::Info, "Checking for wildcard DNS...");
+        if wildcards.fingerprint(&self.client, domain).is_some() {
+            self.notice(Level::Warning, &format!("⚠️ Wildcard DNS detected for *.{}, matching hits will be filtered", domain));
+        }
+
+        self.notice(Level::Info, "Starting scan...");
+
+        let known: HashSet<&str> = found_domains.iter().map(|record| record.hostname.as_str()).collect();
+        let scanned = match (checkpoint, &self.checkpoint) {
+            (Some(checkpoint), Some(path)) => {
+                self.scan_wordlist_checkpointed(wildcards, outcomes, domain, &known, checkpoint, path)
+            },
+            _ => {
+                let candidates: Vec<String> = self
+                    .wordlist
+                    .iter()
+                    .map(|subdomain| format!("{}.{}", subdomain, domain))
+                    .filter(|candidate| !known.contains(candidate.as_str()))
+                    .collect();
+                self.scan_candidates(wildcards, outcomes, &candidates, Source::Bruteforce)
+            },
+        };
+        found_domains.extend(scanned);
+        found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        if let Some(policy) = self.recursion.as_ref().filter(|_| !self.is_stopped()) {
+            found_domains.extend(self.scan_recursive(policy, wildcards, outcomes, &found_domains));
+            found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        }
+        if let Some(permutator) = self.permutator.as_ref().filter(|_| !self.is_stopped()) {
+            let found: Vec<String> = found_domains.iter().map(|record| record.hostname.clone()).collect();
+            let candidates = permutator.generate(domain, &found);
+            if !candidates.is_empty() {
+                self.notice(Level::Info, &format!("Resolving {} permutations of discovered names...", candidates.len()));
+                found_domains.extend(self.scan_candidates(wildcards, outcomes, &candidates, Source::Permutation));
+                found_domains.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+            }
+        }
+        // Later passes can reach names the zone walk already listed
+        found_domains.dedup_by(|a, b| a.hostname == b.hostname);
+        found_domains
+    }
+
+    /// The wordlist pass, skipping the entries `checkpoint` already covers and saving it to `path`
+    /// as lookups finish. Hosts found before the checkpoint was taken are reported again.
+    fn scan_wordlist_checkpointed(
+        &self,
+        wildcards: &WildcardDetector,
+        outcomes: &OutcomeCounts,
+        domain: &str,
+        known: &HashSet<&str>,
+        mut checkpoint: Checkpoint,
+        path: &Path
+    ) -> Vec<HostRecord> {
+        // Transient failures go first, then whatever the checkpoint has not reached
+        let mut candidates: Vec<String> = checkpoint.retry.iter().cloned().collect();
+        let mut offsets = HashMap::new();
+        for (index, subdomain) in self.wordlist.iter().enumerate() {
+            if checkpoint.is_done(index) {
+                continue;
+            }
+            let candidate = format!("{}.{}", subdomain, domain);
+            // Known hosts, retries and repeated entries need no lookup of their own
+            if known.contains(candidate.as_str()) || checkpoint.retry.contains(&candidate) || offsets.contains_key(&candidate) {
+                checkpoint.complete(index);
+            } else {
+                offsets.insert(candidate.clone(), index);
+                candidates.push(candidate);
+            }
+        }
+
+        let restored = checkpoint.results.clone();
+        for record in &restored {
+            self.report(record);
+        }
+
+        // Save before and after the pass; the tracker saves periodically in between
+        let tracker = Tracker::new(path, checkpoint, offsets);
+        let save = || {
+            if let Err(err) = tracker.save() {
+                self.notice(Level::Warning, &format!("⚠️ Could not save checkpoint: {}", err));
+            }
+        };
+        save();
+        let scanned = self.scan_candidates_tracked(wildcards, outcomes, &candidates, Source::Bruteforce, Some(&tracker));
+        save();
+
+        let mut results: Vec<HostRecord> = restored.into_iter().chain(scanned).collect();
+        results.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+        results
+    }
+}
+
+/// Certificate names of `records` that end in `suffix` and are not yet `known`, adding them to it.
+/// Wildcard entries stand for their parent name.
+fn new_certificate_names(records: &[HostRecord], suffix: &str, known: &mut HashSet<String>) -> Vec<String> {
+    let mut names: Vec<String> = records
+        .iter()
+        .flat_map(|record| &record.http)
+        .flat_map(|probe| &probe.certificate_names)
+        .map(|name| name.trim_start_matches("*.").trim_end_matches('.').to_string())
+        .filter(|name| name.ends_with(suffix) && permutations::is_valid_hostname(name))
+        .filter(|name| known.insert(name.clone()))
+        .collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,170 @@
+//! Subdomain takeover detection for dangling CNAMEs.
+//!
+//! A host that aliases a cloud or SaaS resource nobody owns any more can be
+//! claimed by whoever registers that resource. Hosts are flagged when their
+//! CNAME target no longer exists, or when the provider answers with its
+//! "unclaimed" page from the fingerprint database.
+//!
+//! The dangling check only needs DNS and runs as soon as a host is found.
+//! Matching provider pages needs an HTTP request, so it runs in a later pass
+//! that keeps slow web servers away from the DNS workers.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dns::{DnsClient, RecordType};
+use crate::http;
+use crate::outcome::Outcome;
+use crate::results::HostRecord;
+
+// Shipped fingerprints; --fingerprints replaces them with a local copy
+const BUILTIN_FINGERPRINTS: &str = include_str!("../data/takeover-fingerprints.json");
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        f.write_str(label)
+    }
+}
+
+/// One provider entry of the fingerprint database
+#[derive(Clone, Debug, Deserialize)]
+pub struct Fingerprint {
+    pub service: String,
+    /// CNAME target fragments that identify the provider, such as `.github.io`
+    pub cnames: Vec<String>,
+    /// Body text the provider serves for an unclaimed resource
+    #[serde(default)]
+    pub fingerprints: Vec<String>,
+    /// Whether a CNAME target that does not resolve can be claimed
+    #[serde(default)]
+    pub nxdomain: bool,
+    pub severity: Severity,
+}
+
+#[derive(Clone, Debug)]
+pub struct FingerprintDb {
+    fingerprints: Vec<Fingerprint>,
+}
+
+impl FingerprintDb {
+    pub fn builtin() -> Self {
+        FingerprintDb {
+            fingerprints: serde_json::from_str(BUILTIN_FINGERPRINTS).expect("Invalid built-in fingerprint database"),
+        }
+    }
+
+    /// Load a JSON array of fingerprints
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|err| {
+            io::Error::new(err.kind(), format!("Could not read fingerprint database {}: {}", path, err))
+        })?;
+        let fingerprints = serde_json::from_str(&contents).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Invalid fingerprint database {}: {}", path, err))
+        })?;
+        Ok(FingerprintDb { fingerprints })
+    }
+
+    pub fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fingerprints.is_empty()
+    }
+
+    /// Provider whose CNAME fragments appear in any of `cnames`
+    fn service_for(&self, cnames: &[String]) -> Option<&Fingerprint> {
+        self.fingerprints.iter().find(|fingerprint| {
+            cnames.iter().any(|cname| {
+                let cname = cname.to_lowercase();
+                fingerprint.cnames.iter().any(|fragment| cname.contains(&fragment.to_lowercase()))
+            })
+        })
+    }
+}
+
+/// A potential takeover found on a host
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Takeover {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Last CNAME target in the chain
+    pub cname: String,
+    pub reason: String,
+    pub severity: Severity,
+}
+
+/// Check a host's CNAME chain for a target that no longer exists, using DNS only
+pub fn check_dangling(client: &DnsClient, db: &FingerprintDb, record: &HostRecord) -> Option<Takeover> {
+    let target = record.cnames.last()?;
+    if !record.ipv4.is_empty() || !record.ipv6.is_empty() {
+        return None;
+    }
+    let dangling = [RecordType::A, RecordType::AAAA]
+        .iter()
+        .all(|&rtype| client.lookup(target, rtype).outcome == Outcome::NxDomain);
+    if !dangling {
+        return None;
+    }
+
+    let fingerprint = db.service_for(&record.cnames);
+    // Unknown providers may still let the target domain be registered
+    let severity = match fingerprint {
+        Some(fingerprint) if fingerprint.nxdomain => fingerprint.severity,
+        Some(_) => Severity::Low,
+        None => Severity::Medium,
+    };
+    Some(Takeover {
+        service: fingerprint.map(|fingerprint| fingerprint.service.clone()),
+        cname: target.clone(),
+        reason: format!("CNAME target {} does not exist (NXDOMAIN)", target),
+        severity,
+    })
+}
+
+/// Provider with page fingerprints that a live host aliases, if any
+fn page_fingerprint<'a>(db: &'a FingerprintDb, record: &HostRecord) -> Option<&'a Fingerprint> {
+    if record.ipv4.is_empty() && record.ipv6.is_empty() {
+        return None;
+    }
+    db.service_for(&record.cnames).filter(|fingerprint| !fingerprint.fingerprints.is_empty())
+}
+
+/// Whether [`check_page`] has anything to look for on `record`
+pub fn needs_page_check(db: &FingerprintDb, record: &HostRecord) -> bool {
+    page_fingerprint(db, record).is_some()
+}
+
+/// Fetch the host's page and look for its provider's "unclaimed" text
+pub fn check_page(db: &FingerprintDb, record: &HostRecord, timeout: Duration) -> Option<Takeover> {
+    let fingerprint = page_fingerprint(db, record)?;
+    let ip = record.ipv4.first().map(|&ip| IpAddr::V4(ip)).or_else(|| record.ipv6.first().map(|&ip| IpAddr::V6(ip)))?;
+    let response = http::get(SocketAddr::new(ip, 80), &record.hostname, "/", timeout).ok()?;
+    let matched = fingerprint.fingerprints.iter().find(|text| response.body.contains(text.as_str()))?;
+    Some(Takeover {
+        service: Some(fingerprint.service.clone()),
+        cname: record.cnames.last()?.clone(),
+        reason: format!("HTTP {} response contains \"{}\"", response.status, matched),
+        severity: fingerprint.severity,
+    })
+}
@@ -0,0 +1,79 @@
+//! Target domain collection from arguments, files and stdin.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal};
+use std::path::Path;
+
+/// Lowercase a domain and strip surrounding whitespace, a leading `*.` and a trailing dot
+pub fn normalize_domain(value: &str) -> Option<String> {
+    let domain = value
+        .trim()
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_lowercase();
+    if domain.is_empty() || domain.starts_with('#') {
+        None
+    } else {
+        Some(domain)
+    }
+}
+
+/// Read one domain per line; blank lines and `#` comments are skipped
+pub fn read_targets<R: BufRead>(reader: R) -> io::Result<Vec<String>> {
+    let mut targets = Vec::new();
+    for line in reader.lines() {
+        if let Some(domain) = normalize_domain(&line?) {
+            targets.push(domain);
+        }
+    }
+    Ok(targets)
+}
+
+pub fn load_targets_from_file(file_path: &str) -> io::Result<Vec<String>> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Domain list not found at path: {}", file_path),
+        ));
+    }
+    read_targets(BufReader::new(File::open(path)?))
+}
+
+/// Gather targets from positional arguments, a domain list file and stdin.
+///
+/// Stdin is read when `-` is given as a domain, or when nothing else was given
+/// and stdin is not a terminal. Duplicates are dropped, keeping first-seen order.
+pub fn collect_targets(domains: &[String], domain_list: &Option<String>) -> io::Result<Vec<String>> {
+    let mut targets = Vec::new();
+    let mut read_stdin = false;
+
+    for domain in domains {
+        if domain == "-" {
+            read_stdin = true;
+        } else if let Some(domain) = normalize_domain(domain) {
+            targets.push(domain);
+        }
+    }
+    if let Some(path) = domain_list {
+        targets.extend(load_targets_from_file(path)?);
+    }
+    if targets.is_empty() && !io::stdin().is_terminal() {
+        read_stdin = true;
+    }
+    if read_stdin {
+        targets.extend(read_targets(io::stdin().lock())?);
+    }
+
+    let mut seen = HashSet::new();
+    targets.retain(|domain| seen.insert(domain.clone()));
+
+    if targets.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No target domains given. Pass domains as arguments, with --domain-list or on stdin",
+        ));
+    }
+    Ok(targets)
+}
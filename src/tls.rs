@@ -0,0 +1,236 @@
+//! TLS connections for probing.
+//!
+//! Probes want to talk to whatever a host serves, including expired,
+//! self-signed and mismatched certificates, so the chain is not verified.
+//! Handshake signatures still are, using the ring provider's algorithms.
+//! The certificates are kept for the DNS names they list.
+//!
+//! Connections that carry our own data, such as webhook notifications, use
+//! [`connect_verified`] instead, which checks the chain against the webpki roots.
+
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned};
+
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn client_config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let provider = Arc::new(crypto::ring::default_provider());
+            let config = ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()
+                .expect("ring supports the default TLS versions")
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+                .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
+
+fn verified_config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+            let config = ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("ring supports the default TLS versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
+
+/// Connect to `addr` and complete a TLS handshake, sending `host` as SNI
+pub fn connect(addr: SocketAddr, host: &str, timeout: Duration) -> io::Result<TlsStream> {
+    handshake(client_config(), addr, host, timeout)
+}
+
+/// Like [`connect`], but fail unless the certificate chain is valid for `host`
+pub fn connect_verified(addr: SocketAddr, host: &str, timeout: Duration) -> io::Result<TlsStream> {
+    handshake(verified_config(), addr, host, timeout)
+}
+
+fn handshake(config: Arc<ClientConfig>, addr: SocketAddr, host: &str, timeout: Duration) -> io::Result<TlsStream> {
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let connection = ClientConnection::new(config, server_name).map_err(io::Error::other)?;
+
+    let socket = TcpStream::connect_timeout(&addr, timeout)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+
+    let mut stream = StreamOwned::new(connection, socket);
+    while stream.conn.is_handshaking() {
+        stream.conn.complete_io(&mut stream.sock)?;
+    }
+    Ok(stream)
+}
+
+/// DNS names in the subject alternative names of the server's leaf certificate
+pub fn certificate_names(stream: &TlsStream) -> Vec<String> {
+    stream
+        .conn
+        .peer_certificates()
+        .and_then(|chain| chain.first())
+        .and_then(|leaf| subject_alt_names(leaf.as_ref()))
+        .unwrap_or_default()
+}
+
+// Contents of the subjectAltName OID, 2.5.29.17
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+// DER tags walked through on the way to the names
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_EXTENSIONS: u8 = 0xa3;
+const TAG_DNS_NAME: u8 = 0x82;
+
+/// Split the first DER element off `input` as (tag, contents, rest)
+fn der_element(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let len = if first & 0x80 == 0 {
+        usize::from(first)
+    } else {
+        let octets = usize::from(first & 0x7f);
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets].iter().fold(0, |len, &byte| len << 8 | usize::from(byte));
+        rest = &rest[octets..];
+        len
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+/// First element of `input` tagged `wanted`, skipping the ones before it
+fn find_element(mut input: &[u8], wanted: u8) -> Option<&[u8]> {
+    loop {
+        let (tag, contents, rest) = der_element(input)?;
+        if tag == wanted {
+            return Some(contents);
+        }
+        input = rest;
+    }
+}
+
+/// dNSName entries of a certificate's subjectAltName extension (RFC 5280 4.2.1.6)
+fn subject_alt_names(der: &[u8]) -> Option<Vec<String>> {
+    let (_, certificate, _) = der_element(der)?;
+    let (_, tbs_certificate, _) = der_element(certificate)?;
+    let (_, mut extensions, _) = der_element(find_element(tbs_certificate, TAG_EXTENSIONS)?)?;
+
+    while !extensions.is_empty() {
+        let (_, extension, rest) = der_element(extensions)?;
+        extensions = rest;
+        let (_, oid, fields) = der_element(extension)?;
+        if oid != SUBJECT_ALT_NAME {
+            continue;
+        }
+
+        // The value is wrapped in an OCTET STRING, after the optional critical flag
+        let (_, mut general_names, _) = der_element(find_element(fields, TAG_OCTET_STRING)?)?;
+        let mut names = Vec::new();
+        while !general_names.is_empty() {
+            let (tag, contents, rest) = der_element(general_names)?;
+            general_names = rest;
+            if tag == TAG_DNS_NAME {
+                if let Ok(name) = std::str::from_utf8(contents) {
+                    names.push(name.to_lowercase());
+                }
+            }
+        }
+        return Some(names);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn certificate(names: &[&str]) -> Vec<u8> {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        rcgen::generate_simple_self_signed(names).unwrap().cert.der().to_vec()
+    }
+
+    #[test]
+    fn dns_names_are_read_and_addresses_skipped() {
+        let der = certificate(&["WWW.Example.test", "127.0.0.1", "*.api.example.test"]);
+        assert_eq!(subject_alt_names(&der).unwrap(), ["www.example.test", "*.api.example.test"]);
+    }
+
+    #[test]
+    fn truncated_certificates_give_nothing() {
+        let der = certificate(&["www.example.test"]);
+        for len in 0..der.len() {
+            assert_eq!(subject_alt_names(&der[..len]), None, "first {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn long_form_lengths_are_decoded() {
+        let mut input = vec![TAG_OCTET_STRING, 0x81, 200];
+        input.extend([7u8; 200]);
+        input.push(0xff);
+        let (tag, contents, rest) = der_element(&input).unwrap();
+        assert_eq!(tag, TAG_OCTET_STRING);
+        assert_eq!(contents.len(), 200);
+        assert_eq!(rest, [0xff]);
+
+        // Lengths running past the input, and indefinite lengths, are rejected
+        assert_eq!(der_element(&input[..100]), None);
+        assert_eq!(der_element(&[TAG_OCTET_STRING, 0x80, 0, 0]), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Wildcard DNS detection.
+//!
+//! Random labels are resolved under every level a hit appears at. If they
+//! resolve, the answers (addresses, CNAME targets and TTLs) form a fingerprint
+//! and any hit that resolves to the same thing is treated as a wildcard echo.
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::dns::{self, DnsClient, Message, RData, RecordType};
+use crate::outcome::Outcome;
+
+// Random labels resolved per level
+const PROBE_COUNT: usize = 5;
+const PROBE_LABEL_LEN: usize = 12;
+
+#[derive(Clone, Debug, Default)]
+pub struct Fingerprint {
+    pub addresses: BTreeSet<IpAddr>,
+    pub cnames: BTreeSet<String>,
+    /// Answers of other record types (MX, TXT, ...) in presentation format
+    pub records: BTreeSet<String>,
+    pub max_ttl: u32,
+    pub probes_answered: usize,
+}
+
+impl Fingerprint {
+    fn absorb(&mut self, response: &Message) {
+        let summary = summarize(response);
+        self.addresses.extend(summary.addresses);
+        self.cnames.extend(summary.cnames);
+        self.records.extend(summary.records);
+        self.max_ttl = self.max_ttl.max(summary.ttl.unwrap_or(0));
+    }
+
+    /// Whether a response looks like it came from this wildcard.
+    ///
+    /// Caching resolvers only ever count TTLs down, so a record with a TTL
+    /// above anything the wildcard served has its own configuration.
+    pub fn matches(&self, response: &Message) -> bool {
+        let summary = summarize(response);
+        if summary.ttl.is_some_and(|ttl| ttl > self.max_ttl) {
+            return false;
+        }
+        let same_cname = summary.cnames.iter().any(|cname| self.cnames.contains(cname));
+        let same_addresses = !summary.addresses.is_empty()
+            && summary.addresses.is_subset(&self.addresses);
+        let same_records = !summary.records.is_empty()
+            && summary.records.is_subset(&self.records);
+        same_cname || same_addresses || same_records
+    }
+}
+
+struct Summary {
+    addresses: BTreeSet<IpAddr>,
+    cnames: BTreeSet<String>,
+    records: BTreeSet<String>,
+    /// Lowest TTL of the non-CNAME answers
+    ttl: Option<u32>,
+}
+
+fn summarize(response: &Message) -> Summary {
+    let mut summary = Summary {
+        addresses: response.addresses().into_iter().collect(),
+        cnames: BTreeSet::new(),
+        records: BTreeSet::new(),
+        ttl: None,
+    };
+    for record in &response.answers {
+        if let RData::CNAME(target) = &record.data {
+            summary.cnames.insert(target.clone());
+            continue;
+        }
+        summary.ttl = Some(summary.ttl.map_or(record.ttl, |current| current.min(record.ttl)));
+        if !matches!(record.data, RData::A(_) | RData::AAAA(_)) {
+            summary.records.insert(format!("{} {}", record.rtype, record.data));
+        }
+    }
+    summary
+}
+
+pub fn random_label() -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    (0..PROBE_LABEL_LEN)
+        .map(|_| ALPHABET[(dns::random_u64() % ALPHABET.len() as u64) as usize] as char)
+        .collect()
+}
+
+/// Resolve random labels under `level` for each record type and fingerprint whatever answers
+pub fn probe(client: &DnsClient, level: &str, record_types: &[RecordType]) -> Option<Fingerprint> {
+    let mut fingerprint = Fingerprint::default();
+    for _ in 0..PROBE_COUNT {
+        let name = format!("{}.{}", random_label(), level);
+        let mut answered = false;
+        for &rtype in record_types {
+            let lookup = client.lookup(&name, rtype);
+            if let (Outcome::Resolved, Some(response)) = (lookup.outcome, &lookup.response) {
+                fingerprint.absorb(response);
+                answered = true;
+            }
+        }
+        if answered {
+            fingerprint.probes_answered += 1;
+        }
+    }
+
+    (fingerprint.probes_answered > 0).then_some(fingerprint)
+}
+
+/// Per-level wildcard cache shared by the scanning threads
+#[derive(Debug)]
+pub struct WildcardDetector {
+    record_types: Vec<RecordType>,
+    levels: Mutex<HashMap<String, Option<Fingerprint>>>,
+    filtered: AtomicUsize,
+}
+
+impl WildcardDetector {
+    /// Detector probing the record types the scan itself queries
+    pub fn new(record_types: &[RecordType]) -> Self {
+        WildcardDetector {
+            record_types: record_types.to_vec(),
+            levels: Mutex::new(HashMap::new()),
+            filtered: AtomicUsize::new(0),
+        }
+    }
+
+    /// Probe `level` unless it has been probed already
+    pub fn fingerprint(&self, client: &DnsClient, level: &str) -> Option<Fingerprint> {
+        if let Some(known) = self.levels.lock().unwrap().get(level) {
+            return known.clone();
+        }
+        // Probing happens outside the lock; two threads racing on a new level
+        // both probe, which costs a few queries but never blocks the scan.
+        let fingerprint = probe(client, level, &self.record_types);
+        self.levels
+            .lock()
+            .unwrap()
+            .entry(level.to_string())
+            .or_insert(fingerprint)
+            .clone()
+    }
+
+    /// Check a resolved hostname against the wildcard of its parent level,
+    /// counting it as filtered when every response matches
+    pub fn is_wildcard_hit(&self, client: &DnsClient, hostname: &str, responses: &[&Message]) -> bool {
+        let level = match hostname.split_once('.') {
+            Some((_, parent)) => parent,
+            None => return false,
+        };
+        let matched = !responses.is_empty() && self
+            .fingerprint(client, level)
+            .is_some_and(|fingerprint| responses.iter().all(|response| fingerprint.matches(response)));
+        if matched {
+            self.filtered.fetch_add(1, Ordering::Relaxed);
+        }
+        matched
+    }
+
+    pub fn filtered(&self) -> usize {
+        self.filtered.load(Ordering::Relaxed)
+    }
+
+    /// Levels that turned out to have a wildcard, sorted by name
+    pub fn detected(&self) -> Vec<(String, Fingerprint)> {
+        let mut detected: Vec<(String, Fingerprint)> = self
+            .levels
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(level, fingerprint)| {
+                fingerprint.clone().map(|fingerprint| (level.clone(), fingerprint))
+            })
+            .collect();
+        detected.sort_by(|a, b| a.0.cmp(&b.0));
+        detected
+    }
+}
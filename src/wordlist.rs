@@ -0,0 +1,122 @@
+//! Built-in and SecLists wordlists.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::error::{Error, Result};
+
+// Default wordlist of subdomains
+pub const DEFAULT_WORDLIST: &[&str] = &[
+    "www", "mail", "remote", "blog", "webmail", "server", "ns1", "ns2",
+    "smtp", "secure", "vpn", "m", "shop", "ftp", "mail2", "test", "portal",
+    "ns", "ww1", "host", "support", "dev", "web", "bbs", "ww42", "mx", "email",
+    "cloud", "1", "2", "forum", "admin", "api", "cdn", "stage", "gw", "dns",
+    "download", "demo", "dashboard", "app", "beta", "auth", "cms", "testing"
+];
+
+// Potential SecLists wordlist locations
+const POTENTIAL_WORDLIST_PATHS: &[&str] = &[
+    "/usr/share/wordlists/seclists/Discovery/DNS/",
+    "/usr/share/seclists/Discovery/DNS/",
+    "/opt/seclists/Discovery/DNS/",
+    "/usr/local/share/seclists/Discovery/DNS/"
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WordlistType {
+    /// Light wordlist with 45 predefined subdomains
+    Light,
+    /// Top 5000 subdomains from SecLists
+    Top5000,
+    /// Top 20000 subdomains from SecLists
+    Top20000,
+    /// Top 110000 subdomains from SecLists
+    Top110000,
+    /// Custom wordlist file
+    Custom,
+}
+
+/// Find the first existing SecLists wordlist directory
+pub fn find_seclists_path(custom_path: &Option<String>) -> Option<PathBuf> {
+    // First, check if a custom path was provided
+    if let Some(path) = custom_path {
+        let custom_pathbuf = PathBuf::from(path);
+        if custom_pathbuf.exists() {
+            return Some(custom_pathbuf);
+        }
+    }
+
+    // If no custom path or custom path doesn't exist, try predefined paths
+    for potential_path in POTENTIAL_WORDLIST_PATHS {
+        let path = Path::new(potential_path);
+        if path.exists() {
+            return Some(path.to_path_buf());
+        }
+    }
+
+    None
+}
+
+pub fn load_wordlist(
+    wordlist_type: &WordlistType,
+    custom_path: &Option<String>,
+    seclists_path: &Option<String>
+) -> Result<Vec<String>> {
+    match wordlist_type {
+        WordlistType::Light => {
+            Ok(DEFAULT_WORDLIST.iter().map(|&s| s.to_string()).collect())
+        },
+        WordlistType::Top5000 |
+        WordlistType::Top20000 |
+        WordlistType::Top110000 => {
+            // Determine the filename based on wordlist type
+            let filename = match wordlist_type {
+                WordlistType::Top5000 => "subdomains-top1million-5000.txt",
+                WordlistType::Top20000 => "subdomains-top1million-20000.txt",
+                WordlistType::Top110000 => "subdomains-top1million-110000.txt",
+                _ => unreachable!()
+            };
+
+            // Find a valid SecLists path
+            let seclists_base_path = find_seclists_path(seclists_path).ok_or(Error::SecListsNotFound)?;
+
+            let wordlist_path = seclists_base_path.join(filename);
+
+            // Load the wordlist
+            load_wordlist_from_file(&wordlist_path.to_string_lossy())
+        },
+        WordlistType::Custom => {
+            match custom_path {
+                Some(file_path) => load_wordlist_from_file(file_path),
+                None => Err(Error::MissingCustomWordlist)
+            }
+        }
+    }
+}
+
+// Helper function to load wordlist from a file
+pub fn load_wordlist_from_file(file_path: &str) -> Result<Vec<String>> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(Error::WordlistNotFound(file_path.to_string()));
+    }
+
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    // Lines are decoded one at a time so a stray invalid byte costs one entry, not the rest of the file
+    let mut wordlist = Vec::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if !line.trim().is_empty() {
+            wordlist.push(line.to_string());
+        }
+    }
+
+    Ok(wordlist)
+}
@@ -0,0 +1,191 @@
+//! NSEC and NSEC3 zone walking for DNSSEC-signed zones.
+//!
+//! NSEC records link every owner name in a signed zone to the next one, so
+//! following the chain from the apex lists the whole zone. NSEC3 hashes the
+//! names instead; the hashes are gathered from denial responses and cracked
+//! offline against the wordlist.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::thread;
+
+use serde::Serialize;
+
+use crate::dns::{self, DnsClient, Message, RData, RecordType};
+use crate::engine::{Engine, StopHandle};
+use crate::wildcard;
+
+// Upper bound on names followed along an NSEC chain
+const NSEC_MAX_NAMES: usize = 100_000;
+
+// NSEC3 hashes are gathered with batches of random names until a batch
+// turns up nothing new, the chain closes or the query budget runs out
+const NSEC3_BATCH: usize = 64;
+const NSEC3_MAX_QUERIES: usize = 5_000;
+
+// Wordlist entries hashed per work item when cracking; an interrupt is noticed between chunks
+const CRACK_CHUNK: usize = 256;
+
+/// Denial-of-existence scheme a zone is signed with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Denial {
+    Nsec,
+    Nsec3 { salt: Vec<u8>, iterations: u16 },
+}
+
+/// What zone walking found for one target
+#[derive(Clone, Debug, Serialize)]
+pub struct ZoneWalk {
+    /// `nsec` or `nsec3`
+    pub method: String,
+    /// Whether every name in the zone is accounted for
+    pub complete: bool,
+    pub names: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncracked: Option<usize>,
+}
+
+/// Ask for a name that cannot exist and see which denial records come back
+pub fn detect(client: &DnsClient, domain: &str) -> Option<Denial> {
+    let name = format!("{}.{}", wildcard::random_label(), domain);
+    let lookup = client.lookup_dnssec(&name, RecordType::A);
+    lookup.response.iter().flat_map(|response| &response.authority).find_map(|record| match &record.data {
+        RData::NSEC { .. } => Some(Denial::Nsec),
+        RData::NSEC3 { salt, iterations, .. } => Some(Denial::Nsec3 { salt: salt.clone(), iterations: *iterations }),
+        _ => None,
+    })
+}
+
+/// Follow the NSEC chain from the apex.
+/// Returns the names in the zone (apex excluded) and whether the chain led back to the apex
+/// before `stop` was set.
+pub fn walk_nsec(client: &DnsClient, domain: &str, stop: &StopHandle) -> (Vec<String>, bool) {
+    let suffix = format!(".{}", domain);
+    let mut names = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = domain.to_string();
+
+    while names.len() < NSEC_MAX_NAMES && !stop.is_stopped() {
+        let lookup = client.lookup_dnssec(&current, RecordType::NSEC);
+        let next = lookup.response.iter().flat_map(|response| &response.answers).find_map(|record| match &record.data {
+            RData::NSEC { next, .. } if record.name.eq_ignore_ascii_case(&current) => {
+                Some(next.trim_end_matches('.').to_lowercase())
+            },
+            _ => None,
+        });
+
+        match next {
+            Some(next) if next == domain => return (names, true),
+            Some(next) if next.ends_with(&suffix) && seen.insert(next.clone()) => {
+                names.push(next.clone());
+                current = next;
+            },
+            // No answer, a name outside the zone or a loop: the chain is broken
+            _ => break,
+        }
+    }
+    (names, false)
+}
+
+/// NSEC3 hashes of a zone, as lowercase base32hex
+#[derive(Debug, Default)]
+pub struct Nsec3Chain {
+    /// Owner hash -> next hash
+    links: HashMap<String, String>,
+    pub queries: usize,
+}
+
+impl Nsec3Chain {
+    fn absorb(&mut self, response: &Message, domain: &str) -> usize {
+        let suffix = format!(".{}", domain);
+        let before = self.hashes().len();
+        for record in &response.authority {
+            if let RData::NSEC3 { next_hash, .. } = &record.data {
+                if let Some(owner) = record.name.strip_suffix(&suffix) {
+                    self.links.insert(owner.to_lowercase(), dns::base32hex(next_hash));
+                }
+            }
+        }
+        self.hashes().len() - before
+    }
+
+    /// Every hash seen, as an owner or as the next link
+    pub fn hashes(&self) -> HashSet<&str> {
+        self.links.iter().flat_map(|(owner, next)| [owner.as_str(), next.as_str()]).collect()
+    }
+
+    /// The links form a closed ring, so no owner is missing
+    pub fn is_complete(&self) -> bool {
+        !self.links.is_empty() && self.links.values().all(|next| self.links.contains_key(next))
+    }
+}
+
+/// Gather NSEC3 records from denials of random names under `domain`
+pub fn collect_nsec3(client: &DnsClient, engine: &Engine, domain: &str) -> Nsec3Chain {
+    let chain = Mutex::new(Nsec3Chain::default());
+
+    loop {
+        let names: Vec<String> = (0..NSEC3_BATCH)
+            .map(|_| format!("{}.{}", wildcard::random_label(), domain))
+            .collect();
+        let new_hashes = Mutex::new(0);
+        engine.run(&names, |name| {
+            let lookup = client.lookup_dnssec(name, RecordType::A);
+            let mut chain = chain.lock().unwrap();
+            chain.queries += 1;
+            if let Some(response) = &lookup.response {
+                *new_hashes.lock().unwrap() += chain.absorb(response, domain);
+            }
+        });
+
+        let chain = chain.lock().unwrap();
+        if *new_hashes.lock().unwrap() == 0 || chain.is_complete() || chain.queries >= NSEC3_MAX_QUERIES {
+            break;
+        }
+    }
+    chain.into_inner().unwrap()
+}
+
+/// NSEC3 hash of `name` (RFC 5155 5): SHA-1 over the wire-format name and salt, iterated
+pub fn nsec3_hash(name: &str, salt: &[u8], iterations: u16) -> Option<String> {
+    let mut wire = Vec::with_capacity(name.len() + 2);
+    dns::encode_name(&name.to_lowercase(), &mut wire).ok()?;
+
+    let mut digest = hash_with_salt(&wire, salt);
+    for _ in 0..iterations {
+        digest = hash_with_salt(&digest, salt);
+    }
+    Some(dns::base32hex(&digest))
+}
+
+fn hash_with_salt(data: &[u8], salt: &[u8]) -> [u8; 20] {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(data);
+    hasher.update(salt);
+    hasher.digest().bytes()
+}
+
+/// Hash each wordlist entry under `domain` and keep the names whose hash is in the chain.
+/// Chunks of the wordlist are hashed on one worker per CPU; stopping `engine` ends the run between chunks.
+pub fn crack(chain: &Nsec3Chain, domain: &str, salt: &[u8], iterations: u16, wordlist: &[String], engine: &Engine) -> Vec<String> {
+    let hashes = chain.hashes();
+    let chunks: Vec<&[String]> = wordlist.chunks(CRACK_CHUNK).collect();
+    let workers = thread::available_parallelism().map_or(1, |count| count.get());
+    let cracked = Mutex::new(Vec::new());
+
+    engine.with_concurrency(workers).run(&chunks, |chunk| {
+        let names: Vec<String> = chunk
+            .iter()
+            .map(|word| format!("{}.{}", word.trim().to_lowercase(), domain))
+            .filter(|name| nsec3_hash(name, salt, iterations).is_some_and(|hash| hashes.contains(hash.as_str())))
+            .collect();
+        cracked.lock().unwrap().extend(names);
+    });
+
+    let mut cracked = cracked.into_inner().unwrap();
+    cracked.sort();
+    cracked.dedup();
+    cracked
+}
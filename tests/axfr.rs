@@ -0,0 +1,233 @@
+//! Zone transfers against a local nameserver stub.
+//!
+//! The stub answers lookups over UDP and zone transfers over TCP on the same
+//! port, so the CLI can use it both as its resolver and as the nameserver it
+//! asks for the zone.
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+use sub_crawler::dns::{self, RecordType};
+
+const NOERROR: u8 = 0;
+const NXDOMAIN: u8 = 3;
+const REFUSED: u8 = 5;
+
+/// Records of one zone and how its nameserver answers AXFR
+struct Zone {
+    apex: &'static str,
+    records: Vec<(String, RecordType, Vec<u8>)>,
+    /// Messages of the transfer, each a list of records; `None` refuses it
+    transfer: Option<Vec<Vec<Vec<u8>>>>,
+}
+
+fn name(name: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    dns::encode_name(name, &mut out).unwrap();
+    out
+}
+
+fn soa(apex: &str) -> Vec<u8> {
+    let mut rdata = name(&format!("ns1.{}", apex));
+    rdata.extend(name(&format!("hostmaster.{}", apex)));
+    for value in [2024010101u32, 3600, 600, 86400, 300] {
+        rdata.extend(value.to_be_bytes());
+    }
+    rdata
+}
+
+fn a(ip: [u8; 4]) -> Vec<u8> {
+    Ipv4Addr::from(ip).octets().to_vec()
+}
+
+/// One resource record in wire format
+fn wire(owner: &str, rtype: RecordType, rdata: &[u8]) -> Vec<u8> {
+    let mut out = name(owner);
+    out.extend(rtype.code().to_be_bytes());
+    out.extend(1u16.to_be_bytes());
+    out.extend(300u32.to_be_bytes());
+    out.extend((rdata.len() as u16).to_be_bytes());
+    out.extend(rdata);
+    out
+}
+
+/// A response to `query` echoing its question
+fn response(query: &[u8], rcode: u8, answers: &[Vec<u8>]) -> Vec<u8> {
+    let mut end = 12;
+    while query[end] != 0 {
+        end += query[end] as usize + 1;
+    }
+    end += 5;
+
+    let mut out = vec![query[0], query[1], 0x84 | (query[2] & 0x01), 0x80 | rcode];
+    out.extend(1u16.to_be_bytes());
+    out.extend((answers.len() as u16).to_be_bytes());
+    out.extend([0, 0, 0, 0]);
+    out.extend(&query[12..end]);
+    for answer in answers {
+        out.extend(answer);
+    }
+    out
+}
+
+fn lookup(zones: &[Zone], query: &[u8]) -> Vec<u8> {
+    let message = dns::parse_message(query).unwrap();
+    let (qname, qtype) = &message.questions[0];
+    let qname = qname.trim_end_matches('.').to_lowercase();
+    let records: Vec<&(String, RecordType, Vec<u8>)> = zones
+        .iter()
+        .flat_map(|zone| &zone.records)
+        .filter(|(owner, _, _)| *owner == qname)
+        .collect();
+    if records.is_empty() && !zones.iter().any(|zone| zone.apex == qname) {
+        return response(query, NXDOMAIN, &[]);
+    }
+    let answers: Vec<Vec<u8>> = records
+        .iter()
+        .filter(|(_, rtype, _)| rtype == qtype)
+        .map(|(owner, rtype, rdata)| wire(owner, *rtype, rdata))
+        .collect();
+    response(query, NOERROR, &answers)
+}
+
+fn transfer(zones: &[Zone], mut stream: TcpStream) {
+    let mut length = [0u8; 2];
+    if stream.read_exact(&mut length).is_err() {
+        return;
+    }
+    let mut query = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut query).unwrap();
+
+    let message = dns::parse_message(&query).unwrap();
+    let (qname, qtype) = &message.questions[0];
+    let zone = zones.iter().find(|zone| zone.apex == qname.trim_end_matches('.').to_lowercase());
+    let messages = match (zone, qtype) {
+        (Some(zone), RecordType::AXFR) => match &zone.transfer {
+            Some(messages) => messages.iter().map(|answers| response(&query, NOERROR, answers)).collect(),
+            None => vec![response(&query, REFUSED, &[])],
+        },
+        _ => vec![lookup(zones, &query)],
+    };
+    for message in messages {
+        stream.write_all(&(message.len() as u16).to_be_bytes()).unwrap();
+        stream.write_all(&message).unwrap();
+    }
+}
+
+/// Serve `zones` on a free local port, over UDP and TCP, until the test ends
+fn serve(zones: Vec<Zone>) -> u16 {
+    let (udp, tcp) = loop {
+        let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = udp.local_addr().unwrap().port();
+        if let Ok(tcp) = TcpListener::bind(("127.0.0.1", port)) {
+            break (udp, tcp);
+        }
+    };
+    let port = udp.local_addr().unwrap().port();
+    let zones = Arc::new(zones);
+
+    let udp_zones = Arc::clone(&zones);
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok((len, peer)) = udp.recv_from(&mut buf) {
+            let _ = udp.send_to(&lookup(&udp_zones, &buf[..len]), peer);
+        }
+    });
+    thread::spawn(move || {
+        for stream in tcp.incoming().flatten() {
+            let zones = Arc::clone(&zones);
+            thread::spawn(move || transfer(&zones, stream));
+        }
+    });
+    port
+}
+
+/// `hosts` under `apex`, plus the SOA, NS and a nameserver on the stub's address.
+/// With `transfer`, AXFR hands out the zone in two messages, otherwise it is refused.
+fn zone(apex: &'static str, hosts: &[(&str, RecordType, Vec<u8>)], transfer: bool) -> Zone {
+    let nameserver = format!("ns1.{}", apex);
+    let mut records = vec![
+        (apex.to_string(), RecordType::SOA, soa(apex)),
+        (apex.to_string(), RecordType::NS, name(&nameserver)),
+        (nameserver, RecordType::A, a([127, 0, 0, 1])),
+    ];
+    records.extend(hosts.iter().map(|(label, rtype, rdata)| (format!("{}.{}", label, apex), *rtype, rdata.clone())));
+
+    // The SOA opens the first message and closes the second
+    let transfer = transfer.then(|| {
+        let wires: Vec<Vec<u8>> = records.iter().map(|(owner, rtype, rdata)| wire(owner, *rtype, rdata)).collect();
+        let (first, second) = wires.split_at(wires.len() / 2);
+        let mut second = second.to_vec();
+        second.push(wires[0].clone());
+        vec![first.to_vec(), second]
+    });
+    Zone { apex, records, transfer }
+}
+
+/// The report of `domain` and the status output of the scan
+fn scan(domain: &str, port: u16) -> (Value, String) {
+    let resolver = format!("127.0.0.1:{}", port);
+    let output = Command::new(env!("CARGO_BIN_EXE_sub_crawler"))
+        .args([domain, "-r", &resolver, "--axfr-port", &port.to_string()])
+        .args(["--no-history", "--output-format", "json", "--timeout", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let document: Value = serde_json::from_slice(&output.stdout).unwrap();
+    (document["targets"][0].clone(), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+fn hostnames(report: &Value) -> Vec<&str> {
+    report["results"].as_array().unwrap().iter().map(|record| record["hostname"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn open_transfer_is_reported_with_every_record() {
+    let port = serve(vec![zone(
+        "open.test",
+        &[
+            ("www", RecordType::A, a([127, 0, 0, 2])),
+            ("mail", RecordType::A, a([127, 0, 0, 3])),
+            ("vpn", RecordType::A, a([127, 0, 0, 4])),
+            ("legacy", RecordType::CNAME, name("www.open.test")),
+        ],
+        true,
+    )]);
+
+    let (report, status) = scan("open.test", port);
+    assert!(status.contains("Zone transfer allowed by ns1.open.test"), "{}", status);
+    assert_eq!(report["zone_transfers"], serde_json::json!([format!("ns1.open.test (127.0.0.1:{})", port)]));
+    assert_eq!(hostnames(&report), ["legacy.open.test", "mail.open.test", "ns1.open.test", "vpn.open.test", "www.open.test"]);
+
+    let results = report["results"].as_array().unwrap();
+    assert!(results.iter().all(|record| record["source"] == "axfr"));
+    let host = |hostname: &str| results.iter().find(|record| record["hostname"] == hostname).unwrap();
+    // Records from both messages of the transfer made it in
+    assert_eq!(host("www.open.test")["ipv4"], serde_json::json!(["127.0.0.2"]));
+    assert_eq!(host("vpn.open.test")["ipv4"], serde_json::json!(["127.0.0.4"]));
+    assert_eq!(host("legacy.open.test")["cnames"], serde_json::json!(["www.open.test"]));
+}
+
+#[test]
+fn refused_transfer_falls_back_to_the_wordlist() {
+    let port = serve(vec![zone(
+        "closed.test",
+        &[
+            ("www", RecordType::A, a([127, 0, 0, 5])),
+            ("not-in-any-wordlist", RecordType::A, a([127, 0, 0, 6])),
+        ],
+        false,
+    )]);
+
+    let (report, status) = scan("closed.test", port);
+    assert!(status.contains("Zone transfer denied (Refused)"), "{}", status);
+    assert!(report.get("zone_transfers").is_none());
+    let hosts = hostnames(&report);
+    assert!(hosts.contains(&"www.closed.test"), "{:?}", hosts);
+    assert!(!hosts.contains(&"not-in-any-wordlist.closed.test"), "{:?}", hosts);
+    assert!(report["results"].as_array().unwrap().iter().all(|record| record["source"] != "axfr"));
+}
@@ -0,0 +1,177 @@
+//! HTTP probing against local listeners, over plain HTTP and TLS.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use sub_crawler::dns::DnsClient;
+use sub_crawler::probe::{ProbePort, Prober};
+use sub_crawler::ratelimit::RateLimiter;
+use sub_crawler::resolvers::ResolverPool;
+use sub_crawler::{HostRecord, Source};
+
+type Routes = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A full response that closes the connection
+fn page(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+    response
+}
+
+/// Read one request and answer it with whatever `routes` returns for its path
+fn answer(stream: impl Read + Write, routes: &Routes) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+        line.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let _ = reader.get_mut().write_all(routes(path).as_bytes());
+}
+
+fn listener() -> (TcpListener, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    (listener, port)
+}
+
+/// Serve `routes` over plain HTTP
+fn serve(listener: TcpListener, routes: impl Fn(&str) -> String + Send + Sync + 'static) {
+    let routes: Routes = Arc::new(routes);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            answer(stream, &routes);
+        }
+    });
+}
+
+/// Serve `routes` over TLS with a self-signed certificate for `names`
+fn serve_tls(listener: TcpListener, names: &[&str], routes: impl Fn(&str) -> String + Send + Sync + 'static) {
+    let certified = rcgen::generate_simple_self_signed(names.iter().map(|name| name.to_string()).collect::<Vec<_>>()).unwrap();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.signing_key.serialize_der()));
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![CertificateDer::from(certified.cert.der().to_vec())], key)
+        .unwrap();
+    let config = Arc::new(config);
+
+    let routes: Routes = Arc::new(routes);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let connection = ServerConnection::new(Arc::clone(&config)).unwrap();
+            let mut tls = StreamOwned::new(connection, stream);
+            answer(&mut tls, &routes);
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+        }
+    });
+}
+
+/// A client whose resolver is never asked: probed hosts have addresses and redirects use IPs
+fn client() -> DnsClient {
+    let pool = ResolverPool::new(vec!["127.0.0.1:9".parse().unwrap()]).unwrap();
+    DnsClient::new(Arc::new(pool), Arc::new(RateLimiter::new(None, None, 1)), Duration::from_secs(1), 0)
+}
+
+fn host(hostname: &str) -> HostRecord {
+    let mut record = HostRecord::new(hostname, None, Source::Bruteforce);
+    record.ipv4.push(Ipv4Addr::LOCALHOST);
+    record
+}
+
+#[test]
+fn status_title_and_server_are_extracted() {
+    let body = "<html><head><TITLE>\n  Staging    Portal\n</TITLE></head><body>hi</body></html>";
+    let (listener, port) = listener();
+    serve(listener, move |_| page("200 OK", &[("Server", "TestServer/1.0")], body));
+
+    let probes = Prober::new(vec![ProbePort::new(port)], 4, Duration::from_secs(2)).probe(&client(), &host("www.example.test"));
+    assert_eq!(probes.len(), 1);
+    let probe = &probes[0];
+    assert_eq!(probe.url, format!("http://www.example.test:{}/", port));
+    assert_eq!(probe.status, 200);
+    assert_eq!(probe.title.as_deref(), Some("Staging Portal"));
+    assert_eq!(probe.server.as_deref(), Some("TestServer/1.0"));
+    assert_eq!(probe.content_length, body.len());
+    assert!(probe.redirects.is_empty());
+    assert_eq!(probe.final_status, None);
+    assert!(probe.certificate_names.is_empty());
+}
+
+#[test]
+fn ports_that_do_not_answer_are_left_out() {
+    let (closed, closed_port) = listener();
+    drop(closed);
+    let (listener, port) = listener();
+    serve(listener, |_| page("404 Not Found", &[], ""));
+
+    let probes = Prober::new(vec![ProbePort::new(closed_port), ProbePort::new(port)], 4, Duration::from_secs(2)).probe(&client(), &host("www.example.test"));
+    assert_eq!(probes.len(), 1);
+    assert_eq!(probes[0].status, 404);
+    assert_eq!(probes[0].title, None);
+}
+
+#[test]
+fn redirects_are_followed_to_the_final_page() {
+    let (listener, port) = listener();
+    serve(listener, move |path| match path {
+        "/" => page("302 Found", &[("Location", "/login"), ("Server", "Edge/1.0")], ""),
+        // Another host, given as an address so no lookup is needed
+        "/login" => page("301 Moved Permanently", &[("Location", &format!("http://127.0.0.1:{}/home", port))], ""),
+        _ => page("200 OK", &[("Server", "Origin/2.0")], "<title>Home</title>"),
+    });
+
+    let probes = Prober::new(vec![ProbePort::new(port)], 4, Duration::from_secs(2)).probe(&client(), &host("www.example.test"));
+    assert_eq!(probes.len(), 1);
+    let probe = &probes[0];
+    assert_eq!(probe.status, 302);
+    assert_eq!(probe.redirects, [format!("http://www.example.test:{}/login", port), format!("http://127.0.0.1:{}/home", port)]);
+    assert_eq!(probe.final_status, Some(200));
+    // Title and server describe the page the redirects ended on
+    assert_eq!(probe.title.as_deref(), Some("Home"));
+    assert_eq!(probe.server.as_deref(), Some("Origin/2.0"));
+}
+
+#[test]
+fn https_locations_are_fetched_over_tls() {
+    let (secure, secure_port) = listener();
+    serve_tls(secure, &["secure.example.test"], |_| page("200 OK", &[("Server", "Tls/1.0")], "<title>Secure</title>"));
+    let (listener, port) = listener();
+    serve(listener, move |_| page("301 Moved Permanently", &[("Location", &format!("https://127.0.0.1:{}/", secure_port))], ""));
+
+    let probes = Prober::new(vec![ProbePort::new(port)], 4, Duration::from_secs(2)).probe(&client(), &host("www.example.test"));
+    assert_eq!(probes.len(), 1);
+    let probe = &probes[0];
+    assert_eq!(probe.redirects, [format!("https://127.0.0.1:{}/", secure_port)]);
+    assert_eq!(probe.final_status, Some(200));
+    assert_eq!(probe.title.as_deref(), Some("Secure"));
+    assert_eq!(probe.server.as_deref(), Some("Tls/1.0"));
+}
+
+#[test]
+fn https_ports_are_probed_over_tls_on_any_port() {
+    let (listener, port) = listener();
+    serve_tls(listener, &["www.example.test", "admin.example.test"], |_| page("200 OK", &[], "<title>Admin</title>"));
+
+    let https: ProbePort = format!("https:{}", port).parse().unwrap();
+    let probes = Prober::new(vec![https], 4, Duration::from_secs(2)).probe(&client(), &host("www.example.test"));
+    assert_eq!(probes.len(), 1);
+    let probe = &probes[0];
+    assert_eq!(probe.url, format!("https://www.example.test:{}/", port));
+    assert_eq!(probe.status, 200);
+    assert_eq!(probe.title.as_deref(), Some("Admin"));
+    assert_eq!(probe.certificate_names, ["www.example.test", "admin.example.test"]);
+}